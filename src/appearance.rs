@@ -0,0 +1,81 @@
+use iced::{futures::never::Never, Subscription};
+
+/// Whether the desktop prefers light or dark applications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+/// The system appearance once it's known, then every change to it.
+///
+/// Only the XDG desktop portal on Linux reports it; elsewhere, and when the
+/// portal isn't running, this never produces anything.
+pub fn changes() -> Subscription<Appearance> {
+    iced::subscription::channel("appearance", 1, |sender| async move {
+        #[cfg(target_os = "linux")]
+        {
+            let _ = portal::forward(sender).await;
+        }
+        #[cfg(not(target_os = "linux"))]
+        drop(sender);
+
+        iced::futures::future::pending::<Never>().await
+    })
+}
+
+/// Whether the desktop asks for animations to be left out.
+///
+/// Read from GNOME's animation setting through the XDG desktop portal, so
+/// it's `false` elsewhere.
+pub async fn reduce_motion() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        matches!(portal::animations_enabled().await, Ok(false))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use ashpd::desktop::settings::{ColorScheme, Settings};
+    use iced::futures::{channel::mpsc::Sender, SinkExt, StreamExt};
+
+    use super::Appearance;
+
+    pub async fn forward(mut sender: Sender<Appearance>) -> ashpd::Result<()> {
+        let settings = Settings::new().await?;
+
+        if let Some(appearance) = from_scheme(settings.color_scheme().await?) {
+            let _ = sender.send(appearance).await;
+        }
+
+        let mut changes = settings.receive_color_scheme_changed().await?;
+
+        while let Some(scheme) = changes.next().await {
+            if let Some(appearance) = from_scheme(scheme) {
+                let _ = sender.send(appearance).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn animations_enabled() -> ashpd::Result<bool> {
+        Settings::new()
+            .await?
+            .read("org.gnome.desktop.interface", "enable-animations")
+            .await
+    }
+
+    fn from_scheme(scheme: ColorScheme) -> Option<Appearance> {
+        match scheme {
+            ColorScheme::PreferDark => Some(Appearance::Dark),
+            ColorScheme::PreferLight => Some(Appearance::Light),
+            ColorScheme::NoPreference => None,
+        }
+    }
+}
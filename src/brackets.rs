@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+/// The default bracket and quote pairs by language name, with `*` for the
+/// languages that aren't listed.
+pub fn default_pairs() -> HashMap<String, String> {
+    [
+        ("*", "()[]{}\"\"''"),
+        // Lifetimes and `'static` would get a quote closed after them
+        ("Rust", "()[]{}\"\""),
+        ("HTML", "()[]{}<>\"\"''"),
+        ("XML", "<>\"\"''"),
+        ("JavaScript", "()[]{}\"\"''``"),
+        ("TypeScript", "()[]{}\"\"''``"),
+        ("Go", "()[]{}\"\"''``"),
+        ("Markdown", "()[]\"\"``"),
+        // Apostrophes are everywhere in prose
+        ("Plain Text", "()[]{}\"\""),
+    ]
+    .into_iter()
+    .map(|(language, pairs)| (language.to_string(), pairs.to_string()))
+    .collect()
+}
+
+/// The pairs of the language, read from side-by-side opening and closing
+/// characters like `()[]`.
+pub fn pairs(table: &HashMap<String, String>, language: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = table
+        .get(language)
+        .or_else(|| table.get("*"))
+        .map(|pairs| pairs.chars().collect())
+        .unwrap_or_default();
+
+    chars
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+/// What typing a character does, given the characters around the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Typed {
+    /// It's the closing character that's already next, so it's stepped over.
+    Over,
+    /// It opens a pair, whose closing character goes after the cursor.
+    Close(char),
+    /// It's typed as it is.
+    Plain,
+}
+
+pub fn typed(pairs: &[(char, char)], c: char, before: Option<char>, after: Option<char>) -> Typed {
+    if after == Some(c) && pairs.iter().any(|&(_, close)| close == c) {
+        return Typed::Over;
+    }
+
+    let Some(&(open, close)) = pairs.iter().find(|&&(open, _)| open == c) else {
+        return Typed::Plain;
+    };
+
+    // Closing only where nothing follows that the pair would swallow, and
+    // for quotes, not right after a word, as in "don't"
+    let is_free = after.is_none_or(|after| {
+        after.is_whitespace() || pairs.iter().any(|&(_, close)| close == after)
+    });
+    let is_quote = open == close;
+    let is_after_word = before.is_some_and(|before| before.is_alphanumeric() || before == '_');
+
+    if is_free && !(is_quote && is_after_word) {
+        Typed::Close(close)
+    } else {
+        Typed::Plain
+    }
+}
+
+/// The line and byte column of the bracket matching the one at (or else
+/// just before) the position. Quotes, which open and close alike, don't
+/// match anything.
+pub fn matching(
+    lines: &[&str],
+    (line, column): (usize, usize),
+    pairs: &[(char, char)],
+) -> Option<(usize, usize)> {
+    let text = lines.get(line)?;
+    let at = text[column.min(text.len())..]
+        .chars()
+        .next()
+        .map(|c| (column, c));
+    let before = text[..column.min(text.len())].char_indices().next_back();
+
+    let is_bracket = |c: char| {
+        pairs
+            .iter()
+            .any(|&(open, close)| open != close && (open == c || close == c))
+    };
+
+    let (column, bracket) = at
+        .filter(|&(_, c)| is_bracket(c))
+        .or(before.filter(|&(_, c)| is_bracket(c)))?;
+
+    let (open, close, forward) = pairs.iter().find_map(|&(open, close)| {
+        if open == close {
+            None
+        } else if open == bracket {
+            Some((open, close, true))
+        } else if close == bracket {
+            Some((open, close, false))
+        } else {
+            None
+        }
+    })?;
+
+    let mut depth = 0usize;
+
+    if forward {
+        let rest = lines[line][column..]
+            .char_indices()
+            .map(move |(index, c)| (line, column + index, c));
+        let below = lines
+            .iter()
+            .enumerate()
+            .skip(line + 1)
+            .flat_map(|(line, text)| text.char_indices().map(move |(index, c)| (line, index, c)));
+
+        for (line, column, c) in rest.chain(below) {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((line, column));
+                }
+            }
+        }
+    } else {
+        let end = column + close.len_utf8();
+        let rest = lines[line][..end]
+            .char_indices()
+            .rev()
+            .map(move |(index, c)| (line, index, c));
+        let above = lines[..line]
+            .iter()
+            .enumerate()
+            .rev()
+            .flat_map(|(line, text)| {
+                text.char_indices()
+                    .rev()
+                    .map(move |(index, c)| (line, index, c))
+            });
+
+        for (line, column, c) in rest.chain(above) {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((line, column));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// What "Wrap selection with" puts around the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pair {
+    Chars(char, char),
+    /// An HTML or XML tag, whose name is asked for.
+    Tag,
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use iced::highlighter;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// A summary of the accepted arguments, printed along with parse errors.
+pub const USAGE: &str = "\
+usage: iced-editor [options] [+line] [path[:line[:column]]]
+
+options:
+  -w, --wait            edit only the given file and exit once its window closes
+      --new             start with an empty buffer
+      --restore-session reopen the file from the last session
+      --readonly        open the file read-only
+      --theme <name>    use the given highlighter theme for this launch
+      --automation <socket>
+                        accept JSON automation commands on a Unix socket";
+
+/// What the editor was asked to do on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    /// The file to open instead of the default one.
+    pub target: Option<Target>,
+    /// Act as `$EDITOR`: edit only the target and exit once its window closes.
+    pub wait: bool,
+    /// Start with an empty buffer.
+    pub new: bool,
+    /// Reopen the file that was open when the editor last exited.
+    pub restore_session: bool,
+    /// Open the file read-only.
+    pub read_only: bool,
+    /// A highlighter theme overriding the configured one.
+    pub theme: Option<highlighter::Theme>,
+    /// Where to listen for automation commands.
+    pub automation: Option<PathBuf>,
+}
+
+/// A file to open, with an optional 1-based line and column to jump to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Target {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Args {
+    /// Parses the arguments (without the program name).
+    ///
+    /// Accepts `path`, `path:line`, `path:line:column` and vim-style
+    /// `+line path`, plus the options listed in [`USAGE`].
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut parsed = Self::default();
+        let mut line = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--wait" | "-w" => parsed.wait = true,
+                "--new" => parsed.new = true,
+                "--restore-session" => parsed.restore_session = true,
+                "--readonly" => parsed.read_only = true,
+                "--theme" => {
+                    let name = args.next().ok_or("--theme needs a theme name")?;
+
+                    parsed.theme = Some(
+                        config::find_theme(&name)
+                            .ok_or_else(|| format!("unknown theme: {name}"))?,
+                    );
+                }
+                "--automation" => {
+                    let path = args.next().ok_or("--automation needs a socket path")?;
+
+                    parsed.automation = Some(PathBuf::from(path));
+                }
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    return Err(format!("unknown option: {arg}"));
+                }
+                _ => {
+                    if let Some(number) = arg.strip_prefix('+') {
+                        line = Some(
+                            parse_number(number).ok_or_else(|| format!("invalid line: {arg}"))?,
+                        );
+                    } else if parsed.target.is_none() {
+                        parsed.target = Some(Target::parse(&arg));
+                    } else {
+                        return Err(format!("unexpected argument: {arg}"));
+                    }
+                }
+            }
+        }
+
+        if let (Some(target), Some(line)) = (parsed.target.as_mut(), line) {
+            target.line = Some(line);
+            target.column = None;
+        } else if line.is_some() {
+            return Err(String::from("+line needs a file to open"));
+        }
+
+        match (&parsed.target, parsed.new, parsed.restore_session) {
+            (None, _, _) if parsed.wait => Err(String::from("--wait needs a file to open")),
+            (Some(_), true, _) | (Some(_), _, true) | (None, true, true) => Err(String::from(
+                "a file, --new and --restore-session can't be combined",
+            )),
+            _ => Ok(parsed),
+        }
+    }
+}
+
+impl Target {
+    /// The 0-based line and character column to jump to, if any.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.line
+            .map(|line| (line - 1, self.column.map_or(0, |column| column - 1)))
+    }
+
+    /// Splits a trailing `:line` or `:line:column` off the argument, unless
+    /// a file with the literal name exists.
+    fn parse(arg: &str) -> Self {
+        let literal = Self {
+            path: PathBuf::from(arg),
+            line: None,
+            column: None,
+        };
+
+        if Path::new(arg).exists() {
+            return literal;
+        }
+
+        let mut parts = arg.rsplitn(3, ':');
+        let last = parts.next().and_then(parse_number);
+        let middle = parts.next();
+        let rest = parts.next();
+
+        match (rest, middle.and_then(parse_number), last) {
+            (Some(path), Some(line), Some(column)) => Self {
+                path: PathBuf::from(path),
+                line: Some(line),
+                column: Some(column),
+            },
+            _ => match (arg.rsplit_once(':'), last) {
+                (Some((path, _)), Some(line)) if !path.is_empty() => Self {
+                    path: PathBuf::from(path),
+                    line: Some(line),
+                    column: None,
+                },
+                _ => literal,
+            },
+        }
+    }
+}
+
+fn parse_number(text: &str) -> Option<usize> {
+    text.parse().ok().filter(|&n| n > 0)
+}
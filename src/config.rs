@@ -0,0 +1,435 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use iced::highlighter;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    appearance::Appearance,
+    brackets,
+    error::EditorError,
+    format::{self, FinalNewline, IndentRules, IndentStyle, LineEnding},
+    transform::Punctuation,
+};
+
+/// Settings and remembered state persisted between launches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Languages chosen by hand for files whose extension doesn't pick one,
+    /// keyed by absolute path.
+    pub language_overrides: HashMap<PathBuf, String>,
+    /// The editor font family, `None` for the built-in monospace font.
+    pub font: Option<String>,
+    /// Font families offered ahead of the installed monospace ones.
+    pub fonts: Vec<String>,
+    /// What the Tab key inserts.
+    pub indent_style: IndentStyle,
+    /// The width of an indentation level, in columns, which is also where
+    /// tab stops fall.
+    pub indent_width: usize,
+    /// How the status bar and other position labels count columns.
+    pub column_display: ColumnDisplay,
+    /// Whether Enter carries the indentation of the line over to the new one.
+    pub auto_indent: bool,
+    /// How Enter indents beyond copying the indentation, by language name.
+    pub indent_rules: HashMap<String, IndentRules>,
+    /// Whether typing an opening bracket or quote adds its closing one, and
+    /// typing that closing one steps over it.
+    pub auto_close: bool,
+    /// Bracket and quote pairs by language name, each written as opening and
+    /// closing characters side by side, like `()[]{}`; `*` covers the
+    /// languages that aren't listed.
+    pub bracket_pairs: HashMap<String, String>,
+    /// Whether Enter on a comment line starts the new line with the comment
+    /// marker, or ` * ` inside a block comment.
+    pub continue_comments: bool,
+    /// Whether a pasted block is re-indented to the line it's pasted into.
+    pub reindent_paste: bool,
+    /// Whether indentation inserted by Enter is removed again when the cursor
+    /// leaves the line without anything typed on it.
+    pub trim_auto_indent: bool,
+    /// Whether Enter removes the spaces and tabs ending the line it leaves.
+    pub trim_trailing_on_enter: bool,
+    /// Whether the cursor's line is left out of the trailing whitespace
+    /// count and of trimming it, since it's likely still being typed on.
+    pub spare_cursor_line: bool,
+    /// What Escape backs out of, tried in order until one of them is there.
+    pub escape_order: Vec<EscapeStep>,
+    /// Whether the whitespace around a selection is left out of what
+    /// selection-driven features work on.
+    pub trim_selection: bool,
+    /// How many lines a notch of the mouse wheel scrolls.
+    pub wheel_scroll_lines: f32,
+    /// Whether wheel scrolling glides to its target instead of jumping there,
+    /// unless the system asks for reduced motion.
+    pub smooth_scroll: bool,
+    /// How fast a drag selection scrolls once past the editor's edge, in lines
+    /// per tick for every 20 pixels beyond it.
+    pub drag_scroll_speed: f32,
+    /// The spaces per level "Format JSON" indents with.
+    pub json_indent: usize,
+    /// Which way "Normalize punctuation" converts quotes and dashes.
+    pub punctuation: Punctuation,
+    /// How many lines "Peek definition" shows from each definition it finds.
+    pub peek_lines: usize,
+    /// How many copied or cut texts "Paste from history" offers.
+    pub clipboard_history: usize,
+    /// The width, in characters, "Reflow paragraph" wraps lines to.
+    pub reflow_column: usize,
+    /// What opening a folder does, from the command line or elsewhere.
+    pub open_folder: OpenFolder,
+    /// What Save does with an empty untitled buffer; Save As always writes.
+    pub empty_save: EmptySave,
+    /// What saving does with the newline at the end of a file.
+    pub final_newline: FinalNewline,
+    /// Final newline policies for particular file extensions.
+    pub final_newline_overrides: HashMap<String, FinalNewline>,
+    /// The line endings a new file gets when first saved with one of these
+    /// extensions, rather than LF.
+    pub new_file_line_endings: HashMap<String, LineEnding>,
+    /// How the highlighter theme is picked.
+    pub theme_mode: ThemeMode,
+    /// Where editing left off when the editor last exited.
+    pub session: Option<Session>,
+    /// How long after a successful save the same contents won't be written
+    /// again, in milliseconds.
+    pub save_debounce_ms: u64,
+    /// What saves a file automatically; nothing does by default.
+    pub auto_save: Vec<AutoSaveTrigger>,
+    /// How long after the last edit an idle auto-save happens, in milliseconds.
+    pub auto_save_idle_ms: u64,
+    /// How long the cursor has to rest before the occurrences of the word
+    /// under it are highlighted, in milliseconds; 0 highlights on every move.
+    pub occurrence_delay_ms: u64,
+    /// How long reading a file may take before opening it fails, in seconds;
+    /// 0 waits forever.
+    pub read_timeout_secs: u64,
+    /// How much of a file "Open tail" and "Load more" read at a time, in
+    /// kilobytes.
+    pub tail_kb: u64,
+    /// The length (in bytes) of a line that turns highlighting off for the
+    /// file, as minified files would otherwise make the editor crawl.
+    pub long_line_threshold: usize,
+    /// The command "Open terminal here" runs in the folder of the file, as
+    /// the program followed by its arguments.
+    pub terminal: Vec<String>,
+    /// The color of the find bar's matches, as `#rrggbb`; unset picks one
+    /// that suits the theme.
+    pub search_match_color: Option<String>,
+    /// The color of the match the find bar is on, as `#rrggbb`; unset picks
+    /// one that suits the theme.
+    pub current_match_color: Option<String>,
+    /// The files or folders that mark the root of a project, looked for in
+    /// the folders above an opened file.
+    pub root_markers: Vec<String>,
+    /// How opaque the window is, from 0.3 (the least that stays readable) to
+    /// 1.0; the desktop shows through where the system composites windows.
+    pub opacity: f32,
+    /// Whether the outline panel is open when the editor starts, as it was
+    /// last toggled.
+    pub show_outline: bool,
+    /// Whether the window stays above other windows.
+    pub always_on_top: bool,
+    /// Whether launching the editor with a file while it's already running
+    /// opens the file there instead of in a new window.
+    pub single_instance: bool,
+    /// Notes attached to lines (0-based), keyed by absolute path. They live
+    /// here only and are never written into the files.
+    pub notes: HashMap<PathBuf, BTreeMap<usize, String>>,
+    /// Recently opened files, most recent first, by absolute path.
+    pub recent_files: Vec<PathBuf>,
+    /// How many recent files are remembered.
+    pub max_recent_files: usize,
+    /// Recent search queries, most recent first.
+    pub search_history: Vec<String>,
+    /// How many search queries are remembered.
+    pub max_search_history: usize,
+}
+
+/// Something transient that Escape closes or clears.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EscapeStep {
+    /// The command palette, symbol list, close prompt and other panels.
+    Overlay,
+    /// The text selection.
+    Selection,
+    /// The error in the status bar and any toast.
+    Notice,
+}
+
+/// Something that saves a file with unsaved changes on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoSaveTrigger {
+    /// No edits for a while.
+    Idle,
+    /// The window losing focus to another application.
+    OnFocusLost,
+}
+
+/// How the column numbers shown for positions are counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnDisplay {
+    /// As drawn, with a tab reaching up to the next tab stop.
+    #[default]
+    Visual,
+    /// One per character, tabs included, as most compilers report them.
+    Characters,
+}
+
+/// What opening a folder, rather than a file, does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OpenFolder {
+    /// Show the file picker in the folder, to pick files from it.
+    #[default]
+    Pick,
+    /// Say that a folder can't be opened.
+    Refuse,
+}
+
+/// What Save does with an untitled buffer that's empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmptySave {
+    /// Nothing gets written, the buffer just stops counting as unsaved.
+    #[default]
+    Skip,
+    /// Ask before creating an empty file.
+    Confirm,
+    /// Pick a name and write the empty file, like any other.
+    Write,
+}
+
+/// How the highlighter theme (and the light or dark look around it) is picked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    /// Follow the system's light or dark appearance.
+    #[default]
+    System,
+    Light,
+    Dark,
+    /// Always use the named theme.
+    Fixed(String),
+}
+
+impl ThemeMode {
+    const LIGHT: highlighter::Theme = highlighter::Theme::InspiredGitHub;
+    const DARK: highlighter::Theme = highlighter::Theme::SolarizedDark;
+
+    /// The theme to use, given the system appearance if it's known.
+    pub fn theme(&self, system: Option<Appearance>) -> highlighter::Theme {
+        match self {
+            Self::System if system == Some(Appearance::Light) => Self::LIGHT,
+            Self::System | Self::Dark => Self::DARK,
+            Self::Light => Self::LIGHT,
+            Self::Fixed(name) => find_theme(name).unwrap_or(Self::DARK),
+        }
+    }
+}
+
+/// The file open at exit and the cursor position in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            language_overrides: HashMap::new(),
+            font: None,
+            fonts: [
+                "Fira Code",
+                "JetBrains Mono",
+                "Cascadia Code",
+                "Source Code Pro",
+            ]
+            .map(String::from)
+            .to_vec(),
+            indent_style: IndentStyle::default(),
+            indent_width: 4,
+            column_display: ColumnDisplay::default(),
+            auto_indent: true,
+            indent_rules: format::default_indent_rules(),
+            auto_close: true,
+            continue_comments: true,
+            bracket_pairs: brackets::default_pairs(),
+            reindent_paste: true,
+            trim_auto_indent: true,
+            trim_trailing_on_enter: false,
+            spare_cursor_line: true,
+            escape_order: vec![
+                EscapeStep::Overlay,
+                EscapeStep::Selection,
+                EscapeStep::Notice,
+            ],
+            trim_selection: true,
+            wheel_scroll_lines: 4.0,
+            smooth_scroll: false,
+            drag_scroll_speed: 1.0,
+            json_indent: 2,
+            punctuation: Punctuation::default(),
+            peek_lines: 4,
+            clipboard_history: 10,
+            reflow_column: 80,
+            open_folder: OpenFolder::default(),
+            empty_save: EmptySave::default(),
+            final_newline: FinalNewline::default(),
+            final_newline_overrides: HashMap::new(),
+            new_file_line_endings: ["bat", "cmd"]
+                .map(|extension| (String::from(extension), LineEnding::CrLf))
+                .into(),
+            theme_mode: ThemeMode::default(),
+            session: None,
+            save_debounce_ms: 1500,
+            auto_save: Vec::new(),
+            auto_save_idle_ms: 2000,
+            occurrence_delay_ms: 300,
+            read_timeout_secs: 30,
+            tail_kb: 64,
+            long_line_threshold: 10_000,
+            terminal: default_terminal(),
+            search_match_color: None,
+            current_match_color: None,
+            root_markers: [".git", "Cargo.toml", "package.json", ".editor-root"]
+                .map(String::from)
+                .into(),
+            opacity: 1.0,
+            show_outline: false,
+            always_on_top: false,
+            single_instance: true,
+            notes: HashMap::new(),
+            recent_files: Vec::new(),
+            max_recent_files: 10,
+            search_history: Vec::new(),
+            max_search_history: 20,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config, or the defaults if there is none or it can't be read.
+    pub fn load() -> Self {
+        config_file()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Moves the file to the front of the recent files, dropping the oldest
+    /// ones past the limit.
+    pub fn remember_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(self.max_recent_files);
+    }
+
+    pub fn remember_search(&mut self, query: String) {
+        self.search_history.retain(|recent| *recent != query);
+        self.search_history.insert(0, query);
+        self.search_history.truncate(self.max_search_history);
+    }
+
+    /// The opacity setting, kept readable.
+    pub fn opacity(&self) -> f32 {
+        self.opacity.clamp(MIN_OPACITY, 1.0)
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        (self.read_timeout_secs > 0).then(|| Duration::from_secs(self.read_timeout_secs))
+    }
+}
+
+/// The least opacity the window can be set to.
+pub const MIN_OPACITY: f32 = 0.3;
+
+/// Reads a color written as `#rrggbb`.
+pub fn parse_color(hex: &str) -> Option<iced::Color> {
+    let hex = hex.strip_prefix('#')?;
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+
+    Some(iced::Color::from_rgb8(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+    ))
+}
+
+pub async fn save_config(config: Config) -> Result<(), EditorError> {
+    let path = config_file().ok_or(EditorError::NoConfigDir)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    // Paths that aren't UTF-8 can't be JSON keys
+    let contents = serde_json::to_string_pretty(&config)
+        .map_err(|err| EditorError::ConfigError(err.to_string()))?;
+
+    // Each save writes a file of its own and swaps it in whole, so saves
+    // running at once can't interleave into a file that no longer parses
+    static SAVES: AtomicUsize = AtomicUsize::new(0);
+    let save = SAVES.fetch_add(1, Ordering::Relaxed);
+    let temporary = path.with_extension(format!("json.{}-{save}.tmp", process::id()));
+
+    fs::write(&temporary, contents).await?;
+
+    if let Err(err) = fs::rename(&temporary, &path).await {
+        let _ = fs::remove_file(&temporary).await;
+
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+fn default_terminal() -> Vec<String> {
+    let command: &[&str] = if cfg!(target_os = "windows") {
+        &["cmd", "/c", "start", "cmd"]
+    } else if cfg!(target_os = "macos") {
+        &["open", "-a", "Terminal", "."]
+    } else {
+        // Debian and derivatives point this at the preferred terminal
+        &["x-terminal-emulator"]
+    };
+
+    command.iter().map(|part| part.to_string()).collect()
+}
+
+/// Looks up a highlighter theme by name, ignoring case, spaces and dashes.
+pub fn find_theme(name: &str) -> Option<highlighter::Theme> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+
+    let name = normalize(name);
+
+    highlighter::Theme::ALL
+        .iter()
+        .copied()
+        .find(|theme| normalize(&theme.to_string()) == name)
+}
+
+/// The directory holding the config and any other editor-owned files.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("iced-editor"))
+}
+
+fn config_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.json"))
+}
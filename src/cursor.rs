@@ -0,0 +1,192 @@
+use std::ops::Range;
+
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+
+/// Moves the cursor of the [`Content`] to the given line and byte column,
+/// clamping to the end of the buffer or line.
+///
+/// The text editor can only be driven through motions, so this walks down
+/// (visual) lines and then right until the target is reached.
+pub fn move_to(content: &mut Content, line: usize, column: usize) {
+    content.perform(Action::Move(Motion::DocumentStart));
+
+    repeat(content, Motion::Down, |(row, _)| row >= line);
+
+    // Laying out the line is what lets the following `Right` motions work
+    content.perform(Action::Move(Motion::Home));
+
+    let (row, _) = content.cursor_position();
+    let column = content.line(row).map_or(0, |line| line.len()).min(column);
+
+    repeat(content, Motion::Right, |(_, col)| col >= column);
+}
+
+/// Selects a byte range of a line, which the next edit then replaces.
+pub fn select(content: &mut Content, line: usize, range: Range<usize>) {
+    move_to(content, line, range.start);
+
+    let length = content
+        .line(line)
+        .and_then(|text| text.get(range).map(|selected| selected.chars().count()))
+        .unwrap_or_default();
+
+    for _ in 0..length {
+        content.perform(Action::Select(Motion::Right));
+    }
+}
+
+/// Performs the motion until the cursor reaches a position satisfying `done`
+/// or stops moving.
+fn repeat(content: &mut Content, motion: Motion, done: impl Fn((usize, usize)) -> bool) {
+    let mut position = content.cursor_position();
+
+    while !done(position) {
+        content.perform(Action::Move(motion));
+
+        let next = content.cursor_position();
+
+        if next == position {
+            break;
+        }
+
+        position = next;
+    }
+}
+
+/// The byte column of a (0-based) character column on a line.
+pub fn byte_column(content: &Content, line: usize, column: usize) -> usize {
+    content.line(line).map_or(0, |text| {
+        text.char_indices()
+            .nth(column)
+            .map_or(text.len(), |(index, _)| index)
+    })
+}
+
+/// The (0-based) column a byte column of a line is drawn at, with each tab
+/// reaching up to the next multiple of the tab width.
+pub fn visual_column(content: &Content, line: usize, column: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+
+    content.line(line).map_or(0, |text| {
+        let prefix = text.get(..column).unwrap_or(&text);
+
+        prefix.chars().fold(0, |column, c| match c {
+            '\t' => column + tab_width - column % tab_width,
+            _ => column + 1,
+        })
+    })
+}
+
+/// The (0-based) character column of a byte column on a line.
+pub fn char_column(content: &Content, line: usize, column: usize) -> usize {
+    content.line(line).map_or(0, |text| {
+        text.get(..column)
+            .map_or_else(|| text.chars().count(), |prefix| prefix.chars().count())
+    })
+}
+
+/// Whether the edit would remove nothing: Backspace at the very start of the
+/// buffer, or Delete at the very end, with nothing selected.
+pub fn is_at_edge(content: &Content, edit: &Edit) -> bool {
+    content.selection().is_none() && at_edge(edit, content.cursor_position(), end(content))
+}
+
+/// Whether the edit at the cursor would remove nothing, given the line and
+/// byte column the buffer ends at.
+fn at_edge(edit: &Edit, cursor: (usize, usize), end: (usize, usize)) -> bool {
+    match edit {
+        Edit::Backspace => cursor == (0, 0),
+        Edit::Delete => cursor >= end,
+        _ => false,
+    }
+}
+
+/// The line and byte column the buffer ends at.
+fn end(content: &Content) -> (usize, usize) {
+    let last = content.line_count().saturating_sub(1);
+
+    (last, content.line(last).map_or(0, |text| text.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_is_past_the_last_character() {
+        assert_eq!(end(&Content::new()), (0, 0));
+        assert_eq!(end(&Content::with_text("a")), (0, 1));
+        assert_eq!(end(&Content::with_text("ab\ncdé")), (1, 4));
+
+        // A newline ending the text doesn't start a line of its own
+        assert_eq!(end(&Content::with_text("ab\n")), (0, 2));
+    }
+
+    #[test]
+    fn at_edge_in_an_empty_buffer() {
+        assert!(at_edge(&Edit::Backspace, (0, 0), (0, 0)));
+        assert!(at_edge(&Edit::Delete, (0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn at_edge_in_a_single_character_buffer() {
+        assert!(at_edge(&Edit::Backspace, (0, 0), (0, 1)));
+        assert!(!at_edge(&Edit::Delete, (0, 0), (0, 1)));
+
+        assert!(!at_edge(&Edit::Backspace, (0, 1), (0, 1)));
+        assert!(at_edge(&Edit::Delete, (0, 1), (0, 1)));
+    }
+
+    #[test]
+    fn at_edge_only_at_offset_zero_and_the_end() {
+        let end = (1, 2);
+
+        assert!(at_edge(&Edit::Backspace, (0, 0), end));
+        assert!(!at_edge(&Edit::Delete, (0, 0), end));
+        assert!(at_edge(&Edit::Delete, (1, 2), end));
+        assert!(!at_edge(&Edit::Backspace, (1, 2), end));
+
+        // The start of a later line still joins it to the one above, and the
+        // end of an earlier one the one below
+        assert!(!at_edge(&Edit::Backspace, (1, 0), end));
+        assert!(!at_edge(&Edit::Delete, (0, 2), end));
+    }
+
+    #[test]
+    fn at_edge_only_for_deleting_edits() {
+        assert!(!at_edge(&Edit::Enter, (0, 0), (0, 0)));
+        assert!(!at_edge(&Edit::Insert('a'), (0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn visual_column_reaches_tabs_to_the_next_stop() {
+        let content = Content::with_text("\t \tx");
+
+        // Byte columns 1 to 4: after the tab, the space, the tab and the x
+        for (tab_width, columns) in [(4, [4, 5, 8, 9]), (2, [2, 3, 4, 5]), (8, [8, 9, 16, 17])] {
+            for (byte, column) in (1..=4).zip(columns) {
+                assert_eq!(
+                    visual_column(&content, 0, byte, tab_width),
+                    column,
+                    "byte {byte} at tab width {tab_width}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn visual_column_counts_characters_without_tabs() {
+        let content = Content::with_text("aé b");
+
+        assert_eq!(visual_column(&content, 0, 0, 4), 0);
+        assert_eq!(visual_column(&content, 0, 3, 4), 2);
+        assert_eq!(visual_column(&content, 0, 5, 4), 4);
+    }
+
+    #[test]
+    fn visual_column_treats_a_tab_width_of_zero_as_one() {
+        let content = Content::with_text("\t\tx");
+
+        assert_eq!(visual_column(&content, 0, 2, 0), 2);
+    }
+}
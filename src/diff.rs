@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// How a row of a side-by-side diff differs between the two sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Equal,
+    Removed,
+    Added,
+    Changed,
+    /// Changed only in whitespace, line endings included.
+    Whitespace,
+}
+
+/// A line of each side (by number and text), aligned for display. The text
+/// keeps a `\r` ending, so line ending changes show up.
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub kind: RowKind,
+    pub left: Option<(usize, String)>,
+    pub right: Option<(usize, String)>,
+}
+
+/// A side-by-side comparison of two files.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub left_path: PathBuf,
+    pub right_path: PathBuf,
+    pub rows: Vec<Row>,
+    /// The index of the first row of every run of differing rows.
+    pub hunks: Vec<usize>,
+    /// Whether rows differing only in whitespace count as equal.
+    pub ignore_whitespace: bool,
+    /// The hunk last navigated to.
+    pub current_hunk: Option<usize>,
+}
+
+impl Comparison {
+    pub fn new(left_path: PathBuf, left: &str, right_path: PathBuf, right: &str) -> Self {
+        let mut comparison = Self {
+            left_path,
+            right_path,
+            rows: rows(left, right),
+            hunks: Vec::new(),
+            ignore_whitespace: false,
+            current_hunk: None,
+        };
+
+        comparison.find_hunks();
+        comparison
+    }
+
+    /// Whether the row counts as a difference, given what's ignored.
+    pub fn differs(&self, row: &Row) -> bool {
+        match row.kind {
+            RowKind::Equal => false,
+            RowKind::Whitespace => !self.ignore_whitespace,
+            _ => true,
+        }
+    }
+
+    pub fn toggle_ignore_whitespace(&mut self) {
+        self.ignore_whitespace = !self.ignore_whitespace;
+        self.find_hunks();
+    }
+
+    fn find_hunks(&mut self) {
+        self.hunks = (0..self.rows.len())
+            .filter(|&i| {
+                self.differs(&self.rows[i]) && (i == 0 || !self.differs(&self.rows[i - 1]))
+            })
+            .collect();
+        self.current_hunk = None;
+    }
+
+    /// Moves to the next hunk (or the previous one, going backwards),
+    /// wrapping around, and returns its first row.
+    pub fn step(&mut self, forward: bool) -> Option<usize> {
+        let count = self.hunks.len();
+
+        if count == 0 {
+            return None;
+        }
+
+        let hunk = match (self.current_hunk, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(hunk), true) => (hunk + 1) % count,
+            (Some(hunk), false) => (hunk + count - 1) % count,
+        };
+
+        self.current_hunk = Some(hunk);
+
+        Some(self.hunks[hunk])
+    }
+}
+
+fn rows(left: &str, right: &str) -> Vec<Row> {
+    let old = split_lines(left);
+    let new = split_lines(right);
+
+    let line = |lines: &[&str], index: usize| Some((index, lines[index].to_string()));
+
+    let mut rows = Vec::new();
+
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        match op {
+            DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } => rows.extend((0..len).map(|i| Row {
+                kind: RowKind::Equal,
+                left: line(&old, old_index + i),
+                right: line(&new, new_index + i),
+            })),
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => rows.extend((old_index..old_index + old_len).map(|i| Row {
+                kind: RowKind::Removed,
+                left: line(&old, i),
+                right: None,
+            })),
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => rows.extend((new_index..new_index + new_len).map(|i| Row {
+                kind: RowKind::Added,
+                left: None,
+                right: line(&new, i),
+            })),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => rows.extend((0..old_len.max(new_len)).map(|i| {
+                let left = (i < old_len).then(|| line(&old, old_index + i)).flatten();
+                let right = (i < new_len).then(|| line(&new, new_index + i)).flatten();
+
+                let kind = match (&left, &right) {
+                    (Some((_, left)), Some((_, right)))
+                        if without_whitespace(left) == without_whitespace(right) =>
+                    {
+                        RowKind::Whitespace
+                    }
+                    _ => RowKind::Changed,
+                };
+
+                Row { kind, left, right }
+            })),
+        }
+    }
+
+    rows
+}
+
+/// The lines of the text, with `\r` left on the lines ending in `\r\n`.
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n')
+        .map(|line| line.strip_suffix('\n').unwrap_or(line))
+        .collect()
+}
+
+fn without_whitespace(line: &str) -> String {
+    line.split_whitespace().collect()
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, path::PathBuf};
 
 use thiserror::Error;
 
@@ -11,6 +11,33 @@ pub enum EditorError {
     // OtherError(String),
     #[error("Open file error")]
     PickFileError,
+
+    #[error("No config directory available")]
+    NoConfigDir,
+
+    #[error("Timed out reading {}", .0.display())]
+    Timeout(PathBuf),
+
+    #[error("{} is a folder, not a file", .0.display())]
+    IsDirectory(PathBuf),
+
+    #[error("No permission to write {}", .0.display())]
+    PermissionDenied(PathBuf),
+
+    #[error("Save the changes before making the buffer read-only")]
+    UnsavedChanges,
+
+    #[error("{0}")]
+    TransformError(String),
+
+    #[error("Couldn't save the settings: {0}")]
+    ConfigError(String),
+
+    #[error("Automation is off: {0}")]
+    AutomationFailed(String),
+
+    #[error("Failed to open {}", list_paths(.0))]
+    LoadFailed(Vec<PathBuf>),
 }
 
 impl Clone for EditorError {
@@ -21,6 +48,25 @@ impl Clone for EditorError {
             }
             // EditorError::OtherError(e) => EditorError::OtherError(e.clone()),
             EditorError::PickFileError => EditorError::PickFileError,
+            EditorError::NoConfigDir => EditorError::NoConfigDir,
+            EditorError::Timeout(path) => EditorError::Timeout(path.clone()),
+            EditorError::IsDirectory(path) => EditorError::IsDirectory(path.clone()),
+            EditorError::PermissionDenied(path) => EditorError::PermissionDenied(path.clone()),
+            EditorError::UnsavedChanges => EditorError::UnsavedChanges,
+            EditorError::TransformError(message) => EditorError::TransformError(message.clone()),
+            EditorError::ConfigError(message) => EditorError::ConfigError(message.clone()),
+            EditorError::AutomationFailed(message) => {
+                EditorError::AutomationFailed(message.clone())
+            }
+            EditorError::LoadFailed(paths) => EditorError::LoadFailed(paths.clone()),
         }
     }
 }
+
+fn list_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
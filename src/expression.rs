@@ -0,0 +1,171 @@
+/// What evaluating a selected expression does with the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The result takes the place of the expression.
+    Replace,
+    /// The result follows the expression, as in `2 * 3 = 6`.
+    Append,
+}
+
+/// Evaluates the expression in the text, keeping the whitespace around it.
+pub fn apply(text: &str, mode: Mode) -> Result<String, String> {
+    let expression = text.trim();
+    let start = text.len() - text.trim_start().len();
+    let (leading, trailing) = (&text[..start], &text[start + expression.len()..]);
+
+    let result = format_number(evaluate(expression)?);
+
+    Ok(match mode {
+        Mode::Replace => format!("{leading}{result}{trailing}"),
+        Mode::Append => format!("{leading}{expression} = {result}{trailing}"),
+    })
+}
+
+/// Evaluates an arithmetic expression: numbers, `+ - * / %`, `^` for powers,
+/// parentheses and unary minus, with the usual precedence.
+fn evaluate(text: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        chars: text.chars().filter(|c| !c.is_whitespace()).collect(),
+        position: 0,
+    };
+
+    let value = parser.sum()?;
+
+    match parser.peek() {
+        None if value.is_finite() => Ok(value),
+        None => Err(String::from("The result isn't a finite number")),
+        Some(c) => Err(format!("Unexpected '{c}' in the expression")),
+    }
+}
+
+/// Formats a result the way it would be typed: whole numbers without a
+/// fraction, the rest without trailing zeros.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let formatted = format!("{value:.10}");
+
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let is_next = self.peek() == Some(c);
+
+        if is_next {
+            self.position += 1;
+        }
+
+        is_next
+    }
+
+    fn sum(&mut self) -> Result<f64, String> {
+        let mut value = self.product()?;
+
+        loop {
+            if self.eat('+') {
+                value += self.product()?;
+            } else if self.eat('-') {
+                value -= self.product()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64, String> {
+        let mut value = self.power()?;
+
+        loop {
+            if self.eat('*') {
+                value *= self.power()?;
+            } else if self.eat('/') {
+                let divisor = self.power()?;
+
+                if divisor == 0.0 {
+                    return Err(String::from("Division by zero"));
+                }
+
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.power()?;
+
+                if divisor == 0.0 {
+                    return Err(String::from("Division by zero"));
+                }
+
+                value %= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Powers bind tighter than unary minus on their left and associate to
+    /// the right, so `-2^2` is -4 and `2^3^2` is 512.
+    fn power(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.power()?);
+        }
+
+        if self.eat('+') {
+            return self.power();
+        }
+
+        let base = self.atom()?;
+
+        if self.eat('^') {
+            Ok(base.powf(self.power()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<f64, String> {
+        if self.eat('(') {
+            let value = self.sum()?;
+
+            return if self.eat(')') {
+                Ok(value)
+            } else {
+                Err(String::from("Missing closing parenthesis"))
+            };
+        }
+
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '.' || c == '_')
+        {
+            self.position += 1;
+        }
+
+        let number: String = self.chars[start..self.position]
+            .iter()
+            .filter(|&&c| c != '_')
+            .collect();
+
+        match self.peek() {
+            _ if !number.is_empty() => number
+                .parse()
+                .map_err(|_| format!("'{number}' isn't a number")),
+            Some(c) => Err(format!("Unexpected '{c}' in the expression")),
+            None => Err(String::from("The expression ends too early")),
+        }
+    }
+}
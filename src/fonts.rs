@@ -0,0 +1,78 @@
+use std::{borrow::Cow, fs, sync::OnceLock};
+
+use iced::{advanced::graphics::text::font_system, Font};
+
+use crate::config;
+
+/// The entry standing for the built-in monospace font.
+pub const DEFAULT: &str = "Default";
+
+/// The font families the editor can be switched to: the built-in default,
+/// any configured families that are installed, then every installed
+/// monospace family.
+///
+/// Fonts dropped into the `fonts` folder of the config directory are loaded
+/// the first time this is called.
+pub fn families(configured: &[String]) -> &'static [String] {
+    static FAMILIES: OnceLock<Vec<String>> = OnceLock::new();
+
+    FAMILIES.get_or_init(|| {
+        let mut font_system = font_system().write().expect("Write font system");
+
+        for bytes in user_fonts() {
+            font_system.load_font(Cow::Owned(bytes));
+        }
+
+        let db = font_system.raw().db();
+
+        let mut monospace: Vec<String> = db
+            .faces()
+            .filter(|face| face.monospaced)
+            .filter_map(|face| face.families.first().map(|(name, _)| name.clone()))
+            .collect();
+
+        monospace.sort_by_key(|name| name.to_lowercase());
+        monospace.dedup();
+
+        let installed = configured.iter().filter(|family| {
+            db.faces()
+                .any(|face| face.families.iter().any(|(name, _)| name == *family))
+        });
+
+        let mut families = vec![String::from(DEFAULT)];
+
+        for family in installed.chain(&monospace) {
+            if !families.contains(family) {
+                families.push(family.clone());
+            }
+        }
+
+        families
+    })
+}
+
+/// The font for a family name, or the default one if it isn't available.
+pub fn font(families: &'static [String], family: &str) -> Font {
+    match families.iter().find(|name| *name == family) {
+        Some(name) if name != DEFAULT => Font::with_name(name),
+        _ => Font::MONOSPACE,
+    }
+}
+
+fn user_fonts() -> Vec<Vec<u8>> {
+    let Some(entries) = config::config_dir().and_then(|dir| fs::read_dir(dir.join("fonts")).ok())
+    else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e.to_lowercase().as_str(), "ttf" | "otf"))
+        })
+        .filter_map(|path| fs::read(path).ok())
+        .collect()
+}
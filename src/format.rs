@@ -0,0 +1,547 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::language::BlockComment;
+
+/// The line terminator a file is written with.
+///
+/// The buffer itself always uses `\n`; files are converted on load and save.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The line ending used by the first line break in the text, if any.
+    pub fn detect(text: &str) -> Option<Self> {
+        let index = text.find('\n')?;
+
+        Some(if text[..index].ends_with('\r') {
+            Self::CrLf
+        } else {
+            Self::Lf
+        })
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Lf => Self::CrLf,
+            Self::CrLf => Self::Lf,
+        }
+    }
+
+    /// Converts buffer text (`\n`-terminated) to this line ending.
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Lf => text.to_string(),
+            Self::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Converts every line ending in the text to `\n`.
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+        })
+    }
+}
+
+/// Whether the Tab key indents with tabs or spaces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndentStyle {
+    Tabs,
+    #[default]
+    Spaces,
+}
+
+/// The next indentation setting in the status bar cycle:
+/// 2, 4 and 8 spaces, then tabs.
+pub fn next_indentation(style: IndentStyle, width: usize) -> (IndentStyle, usize) {
+    match (style, width) {
+        (IndentStyle::Spaces, 2) => (IndentStyle::Spaces, 4),
+        (IndentStyle::Spaces, 4) => (IndentStyle::Spaces, 8),
+        (IndentStyle::Spaces, _) => (IndentStyle::Tabs, width),
+        (IndentStyle::Tabs, _) => (IndentStyle::Spaces, 2),
+    }
+}
+
+/// The whitespace that indents from the given byte column to the next stop.
+pub fn indent_unit(style: IndentStyle, width: usize, column: usize) -> String {
+    match style {
+        IndentStyle::Tabs => String::from("\t"),
+        IndentStyle::Spaces => {
+            let width = width.max(1);
+
+            " ".repeat(width - column % width)
+        }
+    }
+}
+
+/// When Enter indents the new line more or less than the one it was pressed
+/// on, for a language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndentRules {
+    /// Endings of a line (ignoring trailing whitespace) that open a block,
+    /// so the next line is indented a level deeper.
+    pub indent_after: Vec<String>,
+    /// Words starting a line that end a block, so the next line is indented
+    /// a level shallower.
+    pub dedent_after: Vec<String>,
+}
+
+impl IndentRules {
+    /// The indentation of the line after `line`, starting from a copy of
+    /// its own indentation.
+    pub fn next_indentation(
+        &self,
+        line: &str,
+        indentation: &str,
+        style: IndentStyle,
+        width: usize,
+    ) -> String {
+        let code = line.trim();
+
+        if self
+            .indent_after
+            .iter()
+            .any(|end| code.ends_with(end.as_str()))
+        {
+            return format!(
+                "{indentation}{}",
+                indent_unit(style, width, indentation.len())
+            );
+        }
+
+        let first_word = code
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+
+        if self.dedent_after.iter().any(|word| word == first_word) {
+            return match indentation.strip_suffix('\t') {
+                Some(shallower) => shallower.to_string(),
+                None => {
+                    let spaces = indentation.len() - indentation.trim_end_matches(' ').len();
+                    let level = match spaces % width.max(1) {
+                        0 => width.max(1),
+                        partial => partial,
+                    };
+
+                    indentation[..indentation.len() - level.min(spaces)].to_string()
+                }
+            };
+        }
+
+        indentation.to_string()
+    }
+}
+
+/// The rules shipped for a few common languages, by language name.
+pub fn default_indent_rules() -> HashMap<String, IndentRules> {
+    let rules = |indent_after: &[&str], dedent_after: &[&str]| IndentRules {
+        indent_after: indent_after.iter().map(|end| end.to_string()).collect(),
+        dedent_after: dedent_after.iter().map(|word| word.to_string()).collect(),
+    };
+
+    let braces = rules(&["{", "[", "("], &[]);
+
+    let mut defaults: HashMap<String, IndentRules> = [
+        "Rust",
+        "C",
+        "C++",
+        "C#",
+        "Go",
+        "Java",
+        "JavaScript",
+        "TypeScript",
+        "JSON",
+        "CSS",
+        "PHP",
+    ]
+    .into_iter()
+    .map(|language| (language.to_string(), braces.clone()))
+    .collect();
+
+    defaults.insert(
+        String::from("Python"),
+        rules(
+            &[":", "[", "(", "{"],
+            &["return", "pass", "break", "continue", "raise"],
+        ),
+    );
+    defaults.insert(String::from("YAML"), rules(&[":", "|", ">"], &[]));
+    defaults.insert(
+        String::from("Lua"),
+        rules(&["then", "do", "else", "{"], &[]),
+    );
+
+    defaults
+}
+
+/// What a new line starts with to carry on the comment that `before`, the
+/// text ahead of the cursor when Enter was pressed, is in: the indentation
+/// and line comment marker (`//`, or `///` and `//!` for doc comments), or
+/// ` * ` inside a `/* */` comment.
+///
+/// An empty line comment isn't carried on, so Enter on one ends it, and
+/// neither is a `#!` shebang line.
+pub fn comment_continuation(
+    before: &str,
+    line_comment: Option<&str>,
+    block_comment: Option<BlockComment>,
+) -> Option<String> {
+    let indentation = &before[..before.len() - before.trim_start().len()];
+    let rest = &before[indentation.len()..];
+
+    if line_comment == Some("#") && rest.starts_with("#!") {
+        return None;
+    }
+
+    if let Some(marker) = line_comment {
+        let doc = ["///", "//!"]
+            .into_iter()
+            .filter(|_| marker == "//")
+            .find(|doc| rest.starts_with(doc));
+
+        if let Some(marker) = doc.or(Some(marker).filter(|marker| rest.starts_with(marker))) {
+            let comment = &rest[marker.len()..];
+
+            return (!comment.trim().is_empty()).then(|| {
+                let space = if comment.starts_with([' ', '\t']) {
+                    " "
+                } else {
+                    ""
+                };
+
+                format!("{indentation}{marker}{space}")
+            });
+        }
+    }
+
+    let block = block_comment.filter(|comment| comment.open == "/*")?;
+
+    if rest.contains(block.close) {
+        None
+    } else if rest.starts_with(block.open) {
+        Some(format!("{indentation} * "))
+    } else if rest == "*" || rest.starts_with("* ") {
+        Some(format!("{indentation}* "))
+    } else {
+        None
+    }
+}
+
+/// The (0-based) lines whose indentation goes against the setting: tabs when
+/// indenting with spaces, or a full indentation level of spaces when
+/// indenting with tabs.
+pub fn misindented_lines(text: &str, style: IndentStyle, width: usize) -> Vec<usize> {
+    let spaces = " ".repeat(width.max(1));
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let indentation = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+
+            match style {
+                IndentStyle::Spaces => indentation.contains('\t'),
+                IndentStyle::Tabs => indentation.contains(&spaces),
+            }
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The (0-based) lines ending in spaces or tabs.
+pub fn trailing_whitespace_lines(text: &str) -> Vec<usize> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.ends_with([' ', '\t']))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Removes the spaces and tabs ending each line, except the spared one.
+pub fn trim_trailing_whitespace(text: &str, spared: Option<usize>) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            if Some(index) == spared {
+                line
+            } else {
+                line.trim_end_matches([' ', '\t'])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rewrites the indentation of every line to follow the setting, keeping its
+/// width (with tabs counting up to the next stop).
+pub fn normalize_indentation(text: &str, style: IndentStyle, width: usize) -> String {
+    let width = width.max(1);
+
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim_start_matches([' ', '\t']);
+            let columns = columns(&line[..line.len() - content.len()], width);
+
+            indentation(columns, style, width) + content
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Re-indents a pasted block to sit at the line it's pasted into, keeping
+/// the indentation of its lines relative to each other.
+///
+/// `before` is the text of the line ahead of the cursor. The first pasted
+/// line lands at the cursor, so it only loses its own indentation, and only
+/// when the cursor is in the indentation already.
+pub fn reindent_paste(text: &str, before: &str, style: IndentStyle, width: usize) -> String {
+    let width = width.max(1);
+    let mut lines = text.split('\n');
+
+    let Some(first) = lines.next() else {
+        return text.to_string();
+    };
+
+    let rest: Vec<&str> = lines.collect();
+
+    if rest.is_empty() {
+        return text.to_string();
+    }
+
+    let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let is_blank = |line: &&str| line.trim().is_empty();
+
+    // A block copied from the middle of its first line has that line's
+    // indentation missing, so only the others tell its base indentation
+    let first_counts = leading(first) > 0;
+    let base = rest
+        .iter()
+        .copied()
+        .chain(first_counts.then_some(first))
+        .filter(|line| !is_blank(line))
+        .map(|line| columns(&line[..leading(line)], width))
+        .min()
+        .unwrap_or_default();
+
+    let in_indentation = before.trim().is_empty();
+    let target = if in_indentation {
+        before
+    } else {
+        &before[..leading(before)]
+    };
+    let target = columns(target, width);
+
+    let first = if in_indentation {
+        &first[leading(first)..]
+    } else {
+        first
+    };
+
+    let rest = rest.into_iter().map(|line| {
+        if is_blank(&line) {
+            return String::new();
+        }
+
+        let relative = columns(&line[..leading(line)], width).saturating_sub(base);
+
+        indentation(target + relative, style, width) + &line[leading(line)..]
+    });
+
+    std::iter::once(first.to_string())
+        .chain(rest)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The width of leading whitespace in columns, tabs going to the next stop.
+fn columns(indentation: &str, width: usize) -> usize {
+    indentation.chars().fold(0, |column, c| match c {
+        '\t' => column + width - column % width,
+        _ => column + 1,
+    })
+}
+
+/// Whitespace indenting to the given column in the style.
+fn indentation(columns: usize, style: IndentStyle, width: usize) -> String {
+    match style {
+        IndentStyle::Tabs => "\t".repeat(columns / width) + &" ".repeat(columns % width),
+        IndentStyle::Spaces => " ".repeat(columns),
+    }
+}
+
+/// A status bar label for the indentation setting.
+pub fn indentation_label(style: IndentStyle, width: usize) -> String {
+    match style {
+        IndentStyle::Tabs => format!("Tab Size: {width}"),
+        IndentStyle::Spaces => format!("Spaces: {width}"),
+    }
+}
+
+/// What saving does with the newline at the end of the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinalNewline {
+    #[default]
+    Ensure,
+    Strip,
+    /// Keep whatever the file had when it was opened.
+    Preserve,
+}
+
+impl FinalNewline {
+    /// Applies the policy to the buffer text, given as its lines joined by
+    /// `\n` and whether the file ended with a newline when opened.
+    pub fn apply(self, mut text: String, had_final_newline: bool) -> String {
+        let ensure = match self {
+            Self::Ensure => true,
+            Self::Strip => false,
+            Self::Preserve => had_final_newline,
+        };
+
+        if ensure {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+        } else if self == Self::Strip {
+            text.truncate(text.trim_end_matches('\n').len());
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spaces(text: &str, before: &str) -> String {
+        reindent_paste(text, before, IndentStyle::Spaces, 4)
+    }
+
+    #[test]
+    fn reindent_paste_moves_a_block_into_a_nested_one() {
+        assert_eq!(
+            spaces("if a {\n    b();\n}", "        "),
+            "if a {\n            b();\n        }"
+        );
+    }
+
+    #[test]
+    fn reindent_paste_mid_line_keeps_the_first_line_and_indents_to_the_line() {
+        assert_eq!(
+            spaces("foo(\n    1,\n)", "    let x = "),
+            "foo(\n        1,\n    )"
+        );
+    }
+
+    #[test]
+    fn reindent_paste_takes_the_base_from_the_other_lines_when_the_first_lost_its_own() {
+        assert_eq!(spaces("a {\n        b;\n    }", ""), "a {\n    b;\n}");
+    }
+
+    #[test]
+    fn reindent_paste_counts_an_indented_first_line() {
+        assert_eq!(
+            spaces("    a {\n        b;\n    }", "  "),
+            "a {\n      b;\n  }"
+        );
+    }
+
+    #[test]
+    fn reindent_paste_empties_blank_lines() {
+        assert_eq!(spaces("a\n   \n  b", "    "), "a\n\n    b");
+    }
+
+    #[test]
+    fn reindent_paste_converts_to_the_indent_style() {
+        assert_eq!(
+            reindent_paste("x {\n    y\n}", "\t", IndentStyle::Tabs, 4),
+            "x {\n\t\ty\n\t}"
+        );
+        assert_eq!(spaces("x {\n\ty\n}", "    "), "x {\n        y\n    }");
+    }
+
+    #[test]
+    fn reindent_paste_leaves_a_single_line() {
+        assert_eq!(spaces("    a", "        "), "    a");
+    }
+
+    const C_BLOCK: Option<BlockComment> = Some(BlockComment {
+        open: "/*",
+        close: "*/",
+        nests: false,
+    });
+
+    fn continued(before: &str) -> Option<String> {
+        comment_continuation(before, Some("//"), C_BLOCK)
+    }
+
+    #[test]
+    fn comment_continuation_carries_on_line_comments() {
+        assert_eq!(continued("    // a").as_deref(), Some("    // "));
+        assert_eq!(continued("//a").as_deref(), Some("//"));
+    }
+
+    #[test]
+    fn comment_continuation_carries_on_doc_comments() {
+        assert_eq!(continued("/// a").as_deref(), Some("/// "));
+        assert_eq!(continued("  //! a").as_deref(), Some("  //! "));
+    }
+
+    #[test]
+    fn comment_continuation_ends_on_an_empty_line_comment() {
+        assert_eq!(continued("    //"), None);
+        assert_eq!(continued("// "), None);
+        assert_eq!(continued("///"), None);
+    }
+
+    #[test]
+    fn comment_continuation_leaves_code_before_a_comment() {
+        assert_eq!(continued("x(); // a"), None);
+        assert_eq!(continued("x();"), None);
+    }
+
+    #[test]
+    fn comment_continuation_starts_block_comment_lines() {
+        assert_eq!(continued("/* a").as_deref(), Some(" * "));
+        assert_eq!(continued("    /**").as_deref(), Some("     * "));
+    }
+
+    #[test]
+    fn comment_continuation_carries_on_star_lines() {
+        assert_eq!(continued("     * a").as_deref(), Some("     * "));
+        assert_eq!(continued("     *").as_deref(), Some("     * "));
+    }
+
+    #[test]
+    fn comment_continuation_ends_with_the_block_comment() {
+        assert_eq!(continued("     */"), None);
+        assert_eq!(continued("/* a */"), None);
+        assert_eq!(continued(" * a */"), None);
+    }
+
+    #[test]
+    fn comment_continuation_skips_shebangs() {
+        assert_eq!(
+            comment_continuation("#!/usr/bin/env python3", Some("#"), None),
+            None
+        );
+        assert_eq!(
+            comment_continuation("# a", Some("#"), None).as_deref(),
+            Some("# ")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::Range;
+
+use iced::{
+    advanced::text::highlighter::{self, Format},
+    highlighter as syntax, Color, Font, Theme,
+};
+
+use crate::search;
+
+/// Syntax highlighting with every occurrence of a word picked out on top.
+pub struct Highlighter {
+    syntax: syntax::Highlighter,
+    word: Option<String>,
+    search: Option<Search>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub syntax: syntax::Settings,
+    /// The word whose occurrences stand out, usually the one under the cursor.
+    pub word: Option<String>,
+    /// The matches of the find bar, drawn over everything else.
+    pub search: Option<Search>,
+}
+
+/// The find bar's matches to pick out, and the one it's on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    pub query: String,
+    /// The line and byte column where the current match starts.
+    pub current: Option<(usize, usize)>,
+    /// The color of every match; the theme's when unset.
+    pub color: Option<Color>,
+    /// The color of the current match; the theme's when unset.
+    pub current_color: Option<Color>,
+}
+
+pub enum Highlight {
+    Syntax(syntax::Highlight),
+    Occurrence,
+    Match(Option<Color>),
+    CurrentMatch(Option<Color>),
+}
+
+impl Highlight {
+    pub fn to_format(&self, theme: &Theme) -> Format<Font> {
+        match self {
+            Self::Syntax(highlight) => highlight.to_format(),
+            Self::Occurrence => Format {
+                color: Some(theme.palette().primary),
+                font: None,
+            },
+            // Apart from the occurrences' primary color and from each other,
+            // whatever the theme
+            Self::Match(color) => Format {
+                color: Some(color.unwrap_or(theme.palette().success)),
+                font: None,
+            },
+            Self::CurrentMatch(color) => Format {
+                color: Some(color.unwrap_or(theme.palette().danger)),
+                font: None,
+            },
+        }
+    }
+}
+
+impl highlighter::Highlighter for Highlighter {
+    type Settings = Settings;
+    type Highlight = Highlight;
+
+    type Iterator<'a> = Box<dyn Iterator<Item = (Range<usize>, Self::Highlight)> + 'a>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            syntax: syntax::Highlighter::new(&settings.syntax),
+            word: settings.word.clone(),
+            search: settings.search.clone(),
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        // Updating the syntax highlighter restarts it, which is all a new
+        // word needs too
+        self.syntax.update(&new_settings.syntax);
+        self.word = new_settings.word.clone();
+        self.search = new_settings.search.clone();
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.syntax.change_line(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let index = self.syntax.current_line();
+
+        let matches = self
+            .search
+            .as_ref()
+            .and_then(|search| {
+                let (query, ignore_case) = search::fold(&search.query)?;
+
+                Some(
+                    search::line_matches(line, &query, ignore_case)
+                        .into_iter()
+                        .map(|range| {
+                            let highlight = if search.current == Some((index, range.start)) {
+                                Highlight::CurrentMatch(search.current_color)
+                            } else {
+                                Highlight::Match(search.color)
+                            };
+
+                            (range, highlight)
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap_or_default();
+
+        let occurrences = self
+            .word
+            .as_deref()
+            .map(|word| occurrences(line, word))
+            .unwrap_or_default();
+
+        // Later spans win, so the occurrences are drawn over the syntax colors
+        Box::new(
+            self.syntax
+                .highlight_line(line)
+                .map(|(range, highlight)| (range, Highlight::Syntax(highlight)))
+                .chain(
+                    occurrences
+                        .into_iter()
+                        .map(|range| (range, Highlight::Occurrence)),
+                )
+                .chain(matches),
+        )
+    }
+
+    fn current_line(&self) -> usize {
+        self.syntax.current_line()
+    }
+}
+
+/// The word touching the given byte column of the line, if any.
+pub fn word_at(line: &str, column: usize) -> Option<&str> {
+    let column = column.min(line.len());
+
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word_char(c))
+        .last()
+        .map_or(column, |(i, _)| i);
+
+    let end = line[column..]
+        .char_indices()
+        .find(|&(_, c)| !is_word_char(c))
+        .map_or(line.len(), |(i, _)| column + i);
+
+    (start < end).then(|| &line[start..end])
+}
+
+/// The byte ranges of the whole-word matches of `word` in the line.
+fn occurrences(line: &str, word: &str) -> Vec<Range<usize>> {
+    line.match_indices(word)
+        .map(|(start, _)| start..start + word.len())
+        .filter(|range| {
+            !line[..range.start]
+                .chars()
+                .next_back()
+                .is_some_and(is_word_char)
+                && !line[range.end..].chars().next().is_some_and(is_word_char)
+        })
+        .collect()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,261 @@
+use std::{path::PathBuf, sync::mpsc};
+
+use iced::{
+    futures::{never::Never, SinkExt},
+    Subscription,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::Target, config};
+
+/// Hands the target over to an editor that's already running, returning
+/// whether one took it.
+pub fn forward(target: &Target) -> bool {
+    #[cfg(unix)]
+    {
+        unix::forward(target).is_some()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = target;
+        false
+    }
+}
+
+/// Files handed over by later launches, for as long as this instance runs.
+///
+/// Only Unix domain sockets are supported, so elsewhere every launch gets
+/// its own window.
+pub fn launches() -> Subscription<Target> {
+    iced::subscription::channel("launches", 4, |sender| async move {
+        #[cfg(unix)]
+        unix::listen(sender).await;
+        #[cfg(not(unix))]
+        drop(sender);
+
+        iced::futures::future::pending::<Never>().await
+    })
+}
+
+/// A command read from the automation socket.
+///
+/// Clients write one JSON object per line, named by its `command` field, and
+/// get one [`Response`] line back for each:
+///
+/// - `{"command": "get_content"}` answers with the buffer `content`
+/// - `{"command": "get_cursor"}` answers with the 1-based `line` and `column`
+/// - `{"command": "insert", "text": "..."}` types the text at the cursor
+/// - `{"command": "goto", "line": 12, "column": 4}` moves the cursor, with
+///   1-based numbers and an optional column
+/// - `{"command": "save"}` starts saving the buffer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    GetContent,
+    GetCursor,
+    Insert { text: String },
+    Goto { line: usize, column: Option<usize> },
+    Save,
+}
+
+/// The answer to a [`Request`]; fields that don't apply are left out.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            error: Some(message.into()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Where the answer to a [`Request`] goes.
+pub type Reply = mpsc::Sender<Response>;
+
+/// Requests read from an automation socket at the given path, or why the
+/// socket couldn't be set up.
+pub fn automation(path: PathBuf) -> Subscription<Result<(Request, Reply), String>> {
+    iced::subscription::channel(path.clone(), 4, |mut sender| async move {
+        #[cfg(unix)]
+        let result = unix::serve(path, sender.clone()).await;
+        #[cfg(not(unix))]
+        let result = {
+            let _ = path;
+            Err(String::from("automation needs Unix domain sockets"))
+        };
+
+        if let Err(err) = result {
+            let _ = sender.send(Err(err)).await;
+        }
+
+        iced::futures::future::pending::<Never>().await
+    })
+}
+
+fn socket_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("instance.sock"))
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Write},
+        os::unix::{
+            fs::{FileTypeExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+        sync::mpsc as sync_mpsc,
+        thread,
+    };
+
+    use iced::futures::{
+        channel::mpsc::{self, Sender},
+        SinkExt, StreamExt,
+    };
+
+    use super::{Reply, Request, Response};
+    use crate::cli::Target;
+
+    pub fn forward(target: &Target) -> Option<()> {
+        let mut stream = UnixStream::connect(super::socket_path()?).ok()?;
+        let request = serde_json::to_string(target).ok()?;
+
+        writeln!(stream, "{request}").ok()
+    }
+
+    pub async fn listen(mut sender: Sender<Target>) {
+        let Some(path) = super::socket_path() else {
+            return;
+        };
+
+        // Another instance is listening already
+        if UnixStream::connect(&path).is_ok() {
+            return;
+        }
+
+        // Whatever is left at the path belongs to an instance that's gone
+        let _ = fs::remove_file(&path);
+        let _ = path.parent().map(fs::create_dir_all);
+
+        let Ok(listener) = UnixListener::bind(&path) else {
+            return;
+        };
+
+        let (targets, mut received) = mpsc::unbounded();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut line = String::new();
+
+                if BufReader::new(stream).read_line(&mut line).is_ok() {
+                    if let Ok(target) = serde_json::from_str(&line) {
+                        let _ = targets.unbounded_send(target);
+                    }
+                }
+            }
+        });
+
+        while let Some(target) = received.next().await {
+            let _ = sender.send(target).await;
+        }
+    }
+
+    pub async fn serve(
+        path: PathBuf,
+        mut sender: Sender<Result<(Request, Reply), String>>,
+    ) -> Result<(), String> {
+        // Only a socket nobody answers on is left over and safe to replace;
+        // anything else at the path is likely a mistyped argument
+        if let Ok(metadata) = fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} exists and isn't a socket", path.display()));
+            }
+
+            if UnixStream::connect(&path).is_ok() {
+                return Err(format!("{} is in use by another program", path.display()));
+            }
+
+            fs::remove_file(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        }
+
+        let listener =
+            UnixListener::bind(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+        // Whoever can connect can type into the buffer and save it
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+
+        let (requests, mut received) = mpsc::unbounded();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let requests = requests.clone();
+
+                thread::spawn(move || answer(stream, requests));
+            }
+        });
+
+        while let Some(request) = received.next().await {
+            let _ = sender.send(Ok(request)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Answers the requests of one client until it hangs up.
+    fn answer(stream: UnixStream, requests: mpsc::UnboundedSender<(Request, Reply)>) {
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => {
+                    let (reply, answer) = sync_mpsc::channel();
+
+                    if requests.unbounded_send((request, reply)).is_err() {
+                        return;
+                    }
+
+                    answer
+                        .recv()
+                        .unwrap_or_else(|_| Response::error("the editor went away"))
+                }
+                Err(err) => Response::error(format!("invalid request: {err}")),
+            };
+
+            let Ok(response) = serde_json::to_string(&response) else {
+                return;
+            };
+
+            if writeln!(writer, "{response}").is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{path::Path, sync::OnceLock};
+
+use syntect::parsing::SyntaxSet;
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_nonewlines)
+}
+
+/// The token for unhighlighted text.
+pub const PLAIN_TEXT: &str = "txt";
+
+/// Whether the bundled highlighter knows a syntax for the given token.
+pub fn is_supported(token: &str) -> bool {
+    syntaxes().find_syntax_by_token(token).is_some()
+}
+
+/// The display names of every language the highlighter can be set to.
+pub fn names() -> &'static [String] {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+
+    NAMES.get_or_init(|| {
+        let mut names: Vec<String> = syntaxes()
+            .syntaxes()
+            .iter()
+            .filter(|syntax| !syntax.hidden)
+            .map(|syntax| syntax.name.clone())
+            .collect();
+
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    })
+}
+
+/// The display name of the language a token selects.
+pub fn name(token: &str) -> &'static str {
+    syntaxes()
+        .find_syntax_by_token(token)
+        .unwrap_or_else(|| syntaxes().find_syntax_plain_text())
+        .name
+        .as_str()
+}
+
+/// Block comment delimiters, and whether comments nest inside each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockComment {
+    pub open: &'static str,
+    pub close: &'static str,
+    pub nests: bool,
+}
+
+/// The block comment syntax of the language a token selects, if it has one.
+pub fn block_comment(token: &str) -> Option<BlockComment> {
+    let comment = |open, close, nests| BlockComment { open, close, nests };
+
+    Some(match name(token) {
+        "Rust" | "Scala" | "Swift" => comment("/*", "*/", true),
+        "C" | "C++" | "C#" | "Objective-C" | "Objective-C++" | "Java" | "JavaScript"
+        | "TypeScript" | "Go" | "CSS" | "PHP" | "SQL" | "D" | "Groovy" => {
+            comment("/*", "*/", false)
+        }
+        "HTML" | "XML" | "Markdown" => comment("<!--", "-->", false),
+        "Haskell" => comment("{-", "-}", true),
+        "OCaml" => comment("(*", "*)", true),
+        "Lua" => comment("--[[", "]]", false),
+        _ => return None,
+    })
+}
+
+/// The line comment marker of the language a token selects, if it has one.
+pub fn line_comment(token: &str) -> Option<&'static str> {
+    Some(match name(token) {
+        "Rust" | "C" | "C++" | "C#" | "Objective-C" | "Objective-C++" | "Java" | "JavaScript"
+        | "TypeScript" | "Go" | "Swift" | "Scala" | "PHP" | "D" | "Groovy" => "//",
+        "Python" | "Ruby" | "Perl" | "R" | "YAML" | "Makefile" | "Bourne Again Shell (bash)" => "#",
+        "SQL" | "Haskell" | "Lua" => "--",
+        "Lisp" | "Clojure" => ";",
+        "LaTeX" | "TeX" | "Erlang" => "%",
+        _ => return None,
+    })
+}
+
+/// Picks the highlighter token for a file, trying its extension first and
+/// then its first line (shebang or well-known content markers).
+pub fn detect(path: Option<&Path>, first_line: &str) -> Option<String> {
+    path.and_then(Path::extension)
+        .and_then(|e| e.to_str())
+        .filter(|e| is_supported(e))
+        .map(str::to_string)
+        .or_else(|| from_shebang(first_line))
+        .or_else(|| from_content(first_line))
+}
+
+fn from_shebang(first_line: &str) -> Option<String> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+
+    let mut interpreter = program_name(words.next()?);
+
+    if interpreter == "env" {
+        interpreter = program_name(words.find(|w| !w.starts_with('-'))?);
+    }
+
+    let token = match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "sh" | "bash" | "zsh" | "ksh" | "dash" => "sh",
+        "python" => "py",
+        "ruby" => "rb",
+        "perl" => "pl",
+        "node" | "nodejs" | "deno" => "js",
+        "php" => "php",
+        "lua" => "lua",
+        "Rscript" => "r",
+        "tclsh" => "tcl",
+        "groovy" => "groovy",
+        "scala" => "scala",
+        _ => return None,
+    };
+
+    Some(token.to_string())
+}
+
+fn from_content(first_line: &str) -> Option<String> {
+    let line = first_line.trim_start_matches('\u{feff}').trim_start();
+    let lowercase = line.to_ascii_lowercase();
+
+    let token = if line.starts_with("<?php") {
+        "php"
+    } else if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") {
+        "html"
+    } else if line.starts_with("<?xml") {
+        "xml"
+    } else if line.starts_with("diff --git") || line.starts_with("--- ") {
+        "diff"
+    } else {
+        return None;
+    };
+
+    Some(token.to_string())
+}
+
+fn program_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
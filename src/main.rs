@@ -1,39 +1,313 @@
+mod appearance;
+mod brackets;
+mod checksum;
+mod cli;
+mod config;
+mod cursor;
+mod diff;
 mod error;
+mod expression;
+mod fonts;
+mod format;
+mod highlight;
+mod ipc;
+mod language;
+mod palette;
+mod project;
+mod recovery;
+mod search;
+mod symbols;
+mod tail;
+mod templates;
+mod transform;
 use rfd::AsyncFileDialog;
 use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
+use config::Config;
 use error::EditorError;
+use format::LineEnding;
 use iced::{
-    executor, font,
-    highlighter::{self, Highlighter},
-    keyboard, theme,
+    clipboard, event, executor, font,
+    futures::future,
+    highlighter, keyboard, mouse, theme, time,
     widget::{
-        button, column, container, horizontal_space, pick_list, row, text, text_editor, tooltip,
-        Text,
+        button, column, container, horizontal_space, mouse_area, pick_list, row, scrollable,
+        slider, text, text_editor, text_input, tooltip, Text,
     },
-    Application, Command, Element, Font, Settings, Subscription, Theme,
+    window, Application, Background, Color, Command, Element, Font, Point, Settings, Subscription,
+    Theme, Vector,
 };
 use tokio::fs;
 
 fn main() -> iced::Result {
     let fonts = include_bytes!("../fonts/editor-icons.ttf").as_slice();
 
+    let args = match cli::Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // A running editor opens the file instead, unless the caller is waiting
+    // for this process to finish editing it
+    if let Some(target) = args.target.as_ref().filter(|_| !args.wait) {
+        let target = cli::Target {
+            path: absolute_path(&target.path),
+            ..target.clone()
+        };
+
+        if Config::load().single_instance && ipc::forward(&target) {
+            return Ok(());
+        }
+    }
+
+    let wait = args.wait;
+    let save_error = SaveError::default();
+    let recovery = recovery::Handle::default();
+
+    recovery::install_hook(recovery.clone());
+
     Editor::run(Settings {
+        flags: Flags {
+            args,
+            save_error: save_error.clone(),
+            recovery,
+        },
+        window: window::Settings {
+            exit_on_close_request: false,
+            // Only the window's backgrounds decide what shows through, so
+            // the opacity can change while it runs
+            transparent: true,
+            ..window::Settings::default()
+        },
         default_font: font::Font::MONOSPACE,
         fonts: vec![fonts.into()],
         ..Settings::default()
-    })
+    })?;
+
+    // Whoever is waiting on us needs to know their file wasn't written
+    if let Some(err) = save_error.lock().expect("Lock save error").take() {
+        if wait {
+            eprintln!("error: failed to save: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// The error of the last save if it failed, checked by `main` once the
+/// editor exits.
+type SaveError = Arc<Mutex<Option<EditorError>>>;
+
+/// A file's path and contents, as loaded.
+type LoadedFile = (PathBuf, Arc<String>);
+
+#[derive(Default)]
+struct Flags {
+    args: cli::Args,
+    save_error: SaveError,
+    recovery: recovery::Handle,
+}
+
+/// The find bar, while it's open.
+#[derive(Debug, Clone, Default)]
+struct Find {
+    query: String,
+    /// Which way the last Next or Previous went around the end of the
+    /// buffer, and when, for the notice shown for a moment after.
+    wrapped: Option<(search::Direction, Instant)>,
+    /// Whether the last Next or Previous found nothing at all.
+    has_no_matches: bool,
+    /// The line and byte column of the match last stepped to.
+    current: Option<(usize, usize)>,
+    /// The search history entry Up and Down last brought back, and the query
+    /// typed before, which stepping down past the newest entry restores.
+    recalled: Option<(usize, String)>,
+}
+
+/// A mouse drag selection in progress, tracked so it keeps extending (and
+/// scrolling) once the pointer leaves the editor, which stops reporting it.
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    /// The pointer position in the window, once it has moved.
+    pointer: Option<Point>,
+    /// The last position reported by the editor, relative to it.
+    last: Point,
+    /// Where the editor text starts in the window, as of the last report.
+    origin: Option<Point>,
+    /// The lowest position reported, standing in for the editor's bottom edge.
+    bottom: f32,
+}
+
+/// The panels "Hide all panels" put away, to bring back the next time.
+#[derive(Default)]
+struct Panels {
+    outline: bool,
+    peek: Option<(String, Vec<usize>)>,
+    invisibles: Option<Vec<transform::Invisible>>,
+    checksum: Option<checksum::Checksum>,
+    comparison: Option<diff::Comparison>,
+}
+
+impl Panels {
+    fn is_empty(&self) -> bool {
+        !self.outline
+            && self.peek.is_none()
+            && self.invisibles.is_none()
+            && self.checksum.is_none()
+            && self.comparison.is_none()
+    }
+}
+
+/// Brief feedback on saving and other commands, shown in the status bar.
+#[derive(Debug, Clone)]
+enum Toast {
+    Saving,
+    /// Shown until it has faded out, a while after the given time.
+    Done(String, Instant),
+    /// Shown until dismissed.
+    Failed(EditorError),
+}
+
+impl Toast {
+    fn done(message: impl Into<String>) -> Self {
+        Self::Done(message.into(), Instant::now())
+    }
 }
 
+/// How long a finished toast stays up, and how much of that it spends fading.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+const TOAST_FADE: Duration = Duration::from_millis(500);
+
 struct Editor {
     path: Option<PathBuf>,
     content: text_editor::Content,
     error: Option<EditorError>,
     theme: highlighter::Theme,
+    /// Whether the theme was given on the command line, which outranks the
+    /// system appearance.
+    is_theme_pinned: bool,
+    /// A theme for the current buffer alone, set while the theme picker
+    /// affects just this buffer.
+    theme_override: Option<highlighter::Theme>,
+    /// The system's light or dark appearance, once reported.
+    appearance: Option<appearance::Appearance>,
+    language: Option<String>,
+    font: Font,
+    modifiers: keyboard::Modifiers,
+    invisibles: Option<Vec<transform::Invisible>>,
+    line_ending: LineEnding,
+    comparison: Option<diff::Comparison>,
+    pending_goto: Option<(usize, usize)>,
+    pending_read_only: bool,
+    wait: bool,
+    /// The socket automation commands are read from, if enabled.
+    automation: Option<PathBuf>,
+    /// Whether later launches hand their files over to this instance.
+    is_primary: bool,
+    is_confirming_close: bool,
+    is_confirming_empty_save: bool,
+    close_after_save: bool,
+    is_read_only: bool,
+    /// Whether the file can't be written by this user, which keeps it
+    /// read-only until unlocked, as opposed to read-only by choice.
+    is_write_protected: bool,
+    save_error: SaveError,
+    recovery: recovery::Handle,
+    recovered: Option<recovery::Snapshot>,
+    /// Where editing was when the last run ended without exiting cleanly.
+    crashed_session: Option<config::Session>,
+    palette: Option<String>,
+    /// The notes on lines of the buffer, which stay out of the file.
+    notes: BTreeMap<usize, String>,
+    /// The note being written for the cursor line, while it's open.
+    note_draft: Option<String>,
+    /// The tag name being typed to wrap the selection with.
+    tag_draft: Option<String>,
+    /// The project root found above the file, if any.
+    project_root: Option<PathBuf>,
+    /// The project root picked by hand, which wins over the detected one.
+    root_override: Option<PathBuf>,
+    /// The files in the templates directory, as of the last refresh.
+    templates: Vec<PathBuf>,
+    /// Whether the templates are offered for the new buffer, until it's edited.
+    is_offering_templates: bool,
+    /// The last command that changed the text, for "Repeat last command".
+    last_command: Option<Message>,
+    find: Option<Find>,
+    /// Texts recently copied, cut or pasted, most recent first.
+    clipboard_ring: Vec<String>,
+    /// Whether the clipboard history is open to paste from.
+    is_picking_paste: bool,
+    /// The identifier "Peek definition" looked up and the lines declaring it,
+    /// while the popup is open.
+    peek: Option<(String, Vec<usize>)>,
+    /// The filter typed into the symbol list, while it's open.
+    symbol_query: Option<String>,
+    /// The headings shown in the outline panel, while it's open.
+    outline: Option<Vec<symbols::Symbol>>,
+    /// The hash of the buffer the outline was last built from.
+    outline_hash: u64,
+    /// The panels hidden for a clean editing surface, while they are.
+    hidden_panels: Option<Panels>,
+    /// Whether the opacity slider is in the status bar, which it stays once
+    /// the window has been made translucent, even back at full opacity.
+    is_showing_opacity: bool,
+    is_path_menu_open: bool,
+    /// The hash of the contents being written by an in-flight save.
+    saving: Option<u64>,
+    /// When the last successful save happened and the hash of what it wrote.
+    last_save: Option<(Instant, u64)>,
+    toast: Option<Toast>,
+    checksum: Option<checksum::Checksum>,
     is_dirty: bool,
+    /// Files picked along with the open one, waiting to be switched to.
+    queued_files: Vec<LoadedFile>,
+    /// The file offset the buffer starts at, when only the tail of the file
+    /// was loaded.
+    tail: Option<u64>,
+    /// The size of the file when it was last read, while the buffer holds it
+    /// up to there.
+    file_len: Option<u64>,
+    /// Whether new lines appended to the file are loaded as they come, holding
+    /// the read-only state to go back to afterwards.
+    following: Option<bool>,
+    /// The lines whose indentation goes against the indentation setting.
+    misindented: Vec<usize>,
+    /// The lines ending in spaces or tabs.
+    trailing_whitespace: Vec<usize>,
+    /// Whether highlighting is off because the file has lines too long to
+    /// highlight quickly.
+    is_highlighting_paused: bool,
+    /// Whether the file ended with a newline when it was opened.
+    had_final_newline: bool,
+    /// The line whose indentation was inserted by Enter and not typed on since.
+    auto_indented: Option<usize>,
+    /// The mouse drag selection in progress, if any.
+    drag: Option<Drag>,
+    /// The fraction of a line wheel scrolling has yet to move.
+    scroll_remainder: f32,
+    /// The lines smooth scrolling has yet to move.
+    pending_scroll: i32,
+    /// Whether the system asks for animations to be left out.
+    reduce_motion: bool,
+    /// When the buffer was last edited.
+    last_edit: Option<Instant>,
+    /// The word whose occurrences are highlighted.
+    occurrence: Option<String>,
+    /// When the cursor last moved, while its new word is yet to be highlighted.
+    cursor_moved_at: Option<Instant>,
+    config: Config,
 }
 
 #[derive(Debug, Clone)]
@@ -42,211 +316,4365 @@ enum Message {
     New,
     Save,
     FileSaved(Result<PathBuf, EditorError>),
+    NewFileNamed(Result<PathBuf, EditorError>),
+    ConfirmEmptySave,
+    CancelEmptySave,
+    SaveAs,
+    SaveElevated,
+    SaveCopy,
+    CopySaved(Result<PathBuf, EditorError>),
+    FadeToast,
+    DismissToast,
     Open,
     FileOpened(Result<(PathBuf, Arc<String>), EditorError>),
+    WritabilityChecked((PathBuf, bool)),
+    FilesOpened(Result<Vec<Result<LoadedFile, PathBuf>>, EditorError>),
+    OpenNext,
+    Launched(cli::Target),
+    Automation(ipc::Request, ipc::Reply),
+    AutomationFailed(String),
+    FileQueued(Result<LoadedFile, EditorError>),
     ThemeSelected(highlighter::Theme),
+    AppearanceChanged(appearance::Appearance),
+    UseSystemTheme,
+    ToggleAlwaysOnTop,
+    SetOpacity(f32),
+    OpacityChanged(f32),
+    SaveOpacity,
+    ToggleThemeOverride,
+    ResumeHighlighting,
+    LanguageSelected(String),
+    FontSelected(String),
+    ConfigSaved(Result<(), EditorError>),
+    ModifiersChanged(keyboard::Modifiers),
+    PointerMoved(Point),
+    SmoothScroll,
+    ReduceMotion(bool),
+    PointerReleased,
+    DragScroll,
+    GoTo(usize, usize),
+    FindInvisibles,
+    StripInvisibles,
+    CloseInvisibles,
+    ToggleLineEnding,
+    CycleIndentation,
+    Indent,
+    CompareFiles,
+    FilesCompared(Result<diff::Comparison, EditorError>),
+    PreviousHunk,
+    NextHunk,
+    CloseComparison,
+    ToggleIgnoreWhitespace,
+    ToggleReadOnly,
+    AutoSave(config::AutoSaveTrigger),
+    CloseRequested,
+    SaveAndClose,
+    DiscardAndClose,
+    CancelClose,
+    Exit,
+    SnapshotBuffer,
+    CheckedIn,
+    ReopenCrashed,
+    DismissCrashed,
+    RestoreRecovered,
+    DiscardRecovered,
+    OpenPalette,
+    PaletteChanged(String),
+    SubmitPalette,
+    OpenFind,
+    FindChanged(String),
+    FindNext,
+    FindPrevious,
+    SubmitFind,
+    ExpireFindNotice,
+    CloseFind,
+    RecallSearch { older: bool },
+    ClipboardChanged,
+    ClipboardRead(Option<String>),
+    OpenPasteHistory,
+    PasteFromHistory(usize),
+    PeekDefinition,
+    ClosePeek,
+    JumpToMatchingBracket,
+    WrapSelection(brackets::Pair),
+    TagChanged(String),
+    SubmitTag,
+    OpenSymbols,
+    SymbolQueryChanged(String),
+    SubmitSymbols,
+    JumpToSymbol(usize),
+    ToggleOutline,
+    ToggleAllPanels,
+    RefreshOutline,
+    RunCommand(Box<Message>),
+    RepeatLastCommand,
+    Escape,
+    TogglePathMenu,
+    CopyPath,
+    CopyFileName,
+    HighlightOccurrences,
+    OpenTail,
+    TailOpened(Result<(PathBuf, tail::Chunk), EditorError>),
+    LoadMore,
+    ToggleFollow,
+    OpenRecent(PathBuf),
+    EditNote,
+    NoteChanged(String),
+    SubmitNote,
+    RemoveNote,
+    NextNote,
+    RootDetected(Option<PathBuf>),
+    ChooseProjectRoot,
+    ProjectRootChosen(Result<PathBuf, EditorError>),
+    DetectProjectRoot,
+    RefreshTemplates,
+    TemplatesLoaded(Vec<PathBuf>),
+    NewFromTemplate(PathBuf),
+    TemplateLoaded(Result<(PathBuf, Arc<String>), EditorError>),
+    DismissTemplates,
+    ClearRecentFiles,
+    ClearHistory,
+    NormalizeIndentation,
+    ConvertIndentation(format::IndentStyle),
+    TrimTrailingWhitespace,
+    ToggleBlockComment,
+    SqueezeBlankLines,
+    SortByLength { longest_first: bool },
+    ShuffleLines,
+    TransposeChars,
+    TransposeWords,
+    ReflowParagraph,
+    NormalizePunctuation,
+    EncodeSelection(transform::EncodeKind),
+    DecodeSelection(transform::EncodeKind),
+    FormatJson,
+    ComputeChecksum(checksum::Algorithm),
+    ChecksumComputed(Result<checksum::Checksum, EditorError>),
+    CopyChecksum,
+    OpenTerminal,
+    EvaluateExpression(expression::Mode),
+    IncrementNumber,
+    AlignColumns(transform::Separator),
+    DecrementNumber,
+    CloseChecksum,
+    MinifyJson,
+    CheckFollowed,
+    FollowedRead(Result<Option<tail::Chunk>, EditorError>),
+    MoreLoaded(Result<tail::Chunk, EditorError>),
+}
+
+impl Message {
+    /// Whether "Repeat last command" can run it again: the commands that
+    /// change the text at the cursor or selection, as opposed to navigation,
+    /// file handling and settings.
+    fn is_repeatable(&self) -> bool {
+        matches!(
+            self,
+            Message::ToggleBlockComment
+                | Message::SqueezeBlankLines
+                | Message::SortByLength { .. }
+                | Message::ShuffleLines
+                | Message::TransposeChars
+                | Message::TransposeWords
+                | Message::ReflowParagraph
+                | Message::NormalizePunctuation
+                | Message::NormalizeIndentation
+                | Message::ConvertIndentation(_)
+                | Message::TrimTrailingWhitespace
+                | Message::StripInvisibles
+                | Message::EncodeSelection(_)
+                | Message::DecodeSelection(_)
+                | Message::FormatJson
+                | Message::MinifyJson
+                | Message::EvaluateExpression(_)
+                | Message::IncrementNumber
+                | Message::DecrementNumber
+                | Message::AlignColumns(_)
+        )
+    }
 }
 
 impl Application for Editor {
     type Message = Message;
-    type Flags = ();
+    type Flags = Flags;
     type Executor = executor::Default;
     type Theme = Theme;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
+    fn new(
+        Flags {
+            args: flags,
+            save_error,
+            recovery,
+        }: Self::Flags,
+    ) -> (Self, Command<Message>) {
+        let config = Config::load();
+        // Taking over a crashed editor's files is what leaves its snapshot
+        // for this one to load
+        let crashed = recovery::mark_running();
+        let recovered = recovery::load();
+        let font = config.font.as_deref().map_or(Font::MONOSPACE, |family| {
+            fonts::font(fonts::families(&config.fonts), family)
+        });
+
+        let is_theme_pinned = flags.theme.is_some();
+        let theme = flags.theme.unwrap_or_else(|| config.theme_mode.theme(None));
+
+        let session = config.session.clone().filter(|_| flags.restore_session);
+
+        let (pending_goto, path) = match (flags.target, session) {
+            (Some(target), _) => (target.position(), Some(target.path)),
+            (None, Some(session)) => (Some((session.line, session.column)), Some(session.path)),
+            (None, None) if flags.new => (None, None),
+            (None, None) => (None, Some(default_file())),
+        };
+
+        let command = match path {
+            Some(path) => {
+                Command::perform(load_file(path, config.read_timeout()), Message::FileOpened)
+            }
+            None => Command::none(),
+        };
+
+        let level = if config.always_on_top {
+            window::change_level(window::Id::MAIN, window_level(&config))
+        } else {
+            Command::none()
+        };
+
         (
             Self {
                 content: text_editor::Content::new(),
                 error: None,
                 path: None,
-                theme: highlighter::Theme::SolarizedDark,
+                theme,
+                is_theme_pinned,
+                theme_override: None,
+                appearance: None,
+                language: None,
+                font,
+                modifiers: keyboard::Modifiers::default(),
+                invisibles: None,
+                line_ending: LineEnding::default(),
+                comparison: None,
+                pending_goto,
+                pending_read_only: flags.read_only,
+                wait: flags.wait,
+                is_primary: config.single_instance && !flags.wait,
+                automation: flags.automation,
+                is_confirming_close: false,
+                is_confirming_empty_save: false,
+                close_after_save: false,
+                is_read_only: false,
+                is_write_protected: false,
+                save_error,
+                recovery,
+                recovered,
+                crashed_session: crashed.and_then(|sentinel| sentinel.session),
+                palette: None,
+                notes: BTreeMap::new(),
+                note_draft: None,
+                tag_draft: None,
+                project_root: None,
+                root_override: None,
+                templates: Vec::new(),
+                is_offering_templates: false,
+                last_command: None,
+                find: None,
+                clipboard_ring: Vec::new(),
+                is_picking_paste: false,
+                peek: None,
+                symbol_query: None,
+                outline: config.show_outline.then(Vec::new),
+                outline_hash: 0,
+                hidden_panels: None,
+                is_showing_opacity: config.opacity() < 1.0,
+                is_path_menu_open: false,
+                saving: None,
+                last_save: None,
+                toast: None,
+                checksum: None,
                 is_dirty: true,
+                queued_files: Vec::new(),
+                tail: None,
+                file_len: None,
+                following: None,
+                misindented: Vec::new(),
+                trailing_whitespace: Vec::new(),
+                is_highlighting_paused: false,
+                had_final_newline: true,
+                auto_indented: None,
+                drag: None,
+                scroll_remainder: 0.0,
+                pending_scroll: 0,
+                reduce_motion: false,
+                last_edit: None,
+                occurrence: None,
+                cursor_moved_at: None,
+                config,
             },
-            Command::perform(load_file(default_file()), Message::FileOpened),
+            Command::batch([
+                command,
+                level,
+                Command::perform(appearance::reduce_motion(), Message::ReduceMotion),
+                Command::perform(templates::list(), Message::TemplatesLoaded),
+            ]),
         )
     }
 
     fn title(&self) -> String {
-        String::from("Editor")
+        let file_name = self
+            .path
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str());
+
+        let title = match file_name {
+            Some(name) if self.wait => name.to_string(),
+            _ => String::from("Editor"),
+        };
+
+        // iced can't set the platform's own "edited" marker (like the dot in
+        // a macOS close button), so the title carries it for every platform
+        if self.is_dirty {
+            format!("*{title}")
+        } else {
+            title
+        }
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        if message.is_repeatable() {
+            self.last_command = Some(message.clone());
+        }
+
         match message {
+            Message::RepeatLastCommand => {
+                if let Some(command) = self.last_command.clone() {
+                    return self.update(command);
+                }
+            }
             Message::New => {
                 self.path = None;
+                self.theme_override = None;
+                self.tail = None;
+                self.file_len = None;
+                self.following = None;
+                self.had_final_newline = true;
+                self.is_highlighting_paused = false;
+                self.misindented = Vec::new();
                 self.error = None;
+                self.language = None;
+                self.invisibles = None;
+                if let Some(hidden) = &mut self.hidden_panels {
+                    hidden.invisibles = None;
+                }
+                self.line_ending = LineEnding::default();
+                self.is_read_only = false;
+                self.is_write_protected = false;
+                self.last_save = None;
                 self.is_dirty = true;
+                self.auto_indented = None;
+                self.occurrence = None;
+                self.is_offering_templates = !self.templates.is_empty();
+                self.notes = BTreeMap::new();
+                self.note_draft = None;
+                self.tag_draft = None;
                 self.content = text_editor::Content::new();
             }
-            Message::Save => {
-                let contents = self.content.text();
+            Message::EditNote => {
+                let (line, _) = self.content.cursor_position();
+
+                self.note_draft = Some(self.notes.get(&line).cloned().unwrap_or_default());
+
+                return text_input::focus(note_input());
+            }
+            Message::NoteChanged(draft) => {
+                self.note_draft = Some(draft);
+            }
+            Message::SubmitNote => {
+                let (line, _) = self.content.cursor_position();
+
+                match self
+                    .note_draft
+                    .take()
+                    .filter(|note| !note.trim().is_empty())
+                {
+                    Some(note) => {
+                        self.notes.insert(line, note.trim().to_string());
+                    }
+                    None => {
+                        self.notes.remove(&line);
+                    }
+                }
+
+                return self.store_notes();
+            }
+            Message::RemoveNote => {
+                let (line, _) = self.content.cursor_position();
+
+                self.note_draft = None;
+                self.notes.remove(&line);
+
+                return self.store_notes();
+            }
+            Message::NextNote => {
+                let (line, _) = self.content.cursor_position();
+                let next = self
+                    .notes
+                    .range(line + 1..)
+                    .chain(self.notes.range(..=line))
+                    .next()
+                    .map(|(&line, _)| line);
+
+                if let Some(next) = next {
+                    cursor::move_to(&mut self.content, next, 0);
+                }
+            }
+            Message::RootDetected(root) => {
+                self.project_root = root;
+            }
+            Message::ChooseProjectRoot => {
+                return Command::perform(pick_folder(), Message::ProjectRootChosen);
+            }
+            Message::ProjectRootChosen(Ok(root)) => {
+                self.root_override = Some(root);
+            }
+            Message::ProjectRootChosen(Err(EditorError::PickFileError)) => {}
+            Message::ProjectRootChosen(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::DetectProjectRoot => {
+                self.root_override = None;
 
+                return self.detect_root();
+            }
+            Message::RefreshTemplates => {
+                return Command::perform(templates::list(), Message::TemplatesLoaded);
+            }
+            Message::TemplatesLoaded(templates) => {
+                self.templates = templates;
+            }
+            Message::NewFromTemplate(path) => {
                 return Command::perform(
-                    save_file(self.path.clone(), contents),
-                    Message::FileSaved,
+                    load_file(path, self.config.read_timeout()),
+                    Message::TemplateLoaded,
                 );
             }
+            Message::TemplateLoaded(Ok((path, contents))) => {
+                let _ = self.update(Message::New);
+
+                self.is_offering_templates = false;
+                self.line_ending = LineEnding::detect(&contents).unwrap_or_default();
+                self.language = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .filter(|extension| language::is_supported(extension))
+                    .map(str::to_string);
+                self.content =
+                    text_editor::Content::with_text(&format::normalize_line_endings(&contents));
+
+                self.check_indentation();
+            }
+            Message::TemplateLoaded(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::DismissTemplates => {
+                self.is_offering_templates = false;
+            }
+            // Writing a partly loaded file back would truncate it
+            Message::Save if self.tail.is_some() => {}
+            // A reflexive Ctrl+S on a fresh buffer shouldn't leave an empty
+            // file behind
+            Message::Save
+                if self.path.is_none()
+                    && self.buffer_text().is_empty()
+                    && self.config.empty_save != config::EmptySave::Write =>
+            {
+                if self.config.empty_save == config::EmptySave::Confirm {
+                    self.is_confirming_empty_save = true;
+
+                    return Command::none();
+                }
+
+                self.is_dirty = false;
+                self.toast = Some(Toast::done("Nothing to save in an empty buffer"));
+
+                if self.close_after_save {
+                    return self.exit();
+                }
+            }
+            Message::Save => {
+                return self.save();
+            }
+            Message::ConfirmEmptySave => {
+                self.is_confirming_empty_save = false;
+
+                return self.save();
+            }
+            Message::CancelEmptySave => {
+                self.is_confirming_empty_save = false;
+                self.close_after_save = false;
+            }
+            Message::NewFileNamed(Ok(path)) => {
+                let line_ending = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .and_then(|extension| self.config.new_file_line_endings.get(extension));
+
+                if let Some(&line_ending) = line_ending {
+                    self.line_ending = line_ending;
+                }
+
+                let contents = self.line_ending.apply(&self.contents_to_save());
+
+                self.saving = Some(content_hash(&contents));
+
+                return Command::perform(save_file(Some(path), contents), Message::FileSaved);
+            }
+            Message::NewFileNamed(Err(err)) => {
+                return self.update(Message::FileSaved(Err(err)));
+            }
+            Message::SaveAs | Message::SaveElevated if self.saving.is_some() => {}
+            Message::SaveAs => {
+                let contents = self.line_ending.apply(&self.contents_to_save());
+
+                self.saving = Some(content_hash(&contents));
+                self.toast = Some(Toast::Saving);
+
+                return Command::perform(save_file(None, contents), Message::FileSaved);
+            }
+            Message::SaveElevated => {
+                let Some(path) = self.path.clone() else {
+                    return Command::none();
+                };
+
+                let contents = self.line_ending.apply(&self.contents_to_save());
+
+                self.saving = Some(content_hash(&contents));
+                self.toast = Some(Toast::Saving);
+
+                return Command::perform(save_elevated(path, contents), Message::FileSaved);
+            }
+            Message::SaveCopy => {
+                let contents = self.line_ending.apply(&self.contents_to_save());
+
+                self.toast = Some(Toast::Saving);
+
+                return Command::perform(save_copy(contents), Message::CopySaved);
+            }
+            Message::CopySaved(Ok(_)) => {
+                self.toast = Some(Toast::done("Saved"));
+            }
+            // Closing the file dialog isn't worth a toast
+            Message::CopySaved(Err(EditorError::PickFileError)) => {
+                self.toast = None;
+            }
+            Message::CopySaved(Err(err)) => {
+                self.toast = Some(Toast::Failed(err.clone()));
+                self.error = Some(err);
+            }
             Message::FileSaved(Ok(path)) => {
+                // Only UTF-8 is written, but a new file's first save is where
+                // the encoding and line endings get settled
+                self.toast = Some(Toast::done(match self.path {
+                    Some(_) => String::from("Saved"),
+                    None => format!("Saved as UTF-8 with {} line endings", self.line_ending),
+                }));
+                let is_new_path = self.path.as_ref() != Some(&path);
+
+                if is_new_path {
+                    self.is_write_protected = false;
+                }
+
                 self.path = Some(path);
+                self.file_len = None;
                 self.is_dirty = false;
+                self.remember_notes();
+                self.last_save = self.saving.take().map(|hash| (Instant::now(), hash));
+                *self.save_error.lock().expect("Lock save error") = None;
+                *self.recovery.lock().expect("Lock recovery snapshot") = None;
+                recovery::discard();
+
+                if self.close_after_save {
+                    return self.exit();
+                }
+
+                if is_new_path {
+                    return self.detect_root();
+                }
             }
             Message::FileSaved(Err(err)) => {
+                self.saving = None;
+                self.close_after_save = false;
+                self.toast = match err {
+                    EditorError::PickFileError => None,
+                    _ => Some(Toast::Failed(err.clone())),
+                };
+                *self.save_error.lock().expect("Lock save error") = Some(err.clone());
                 self.error = Some(err);
             }
-            Message::Edit(action) => {
-                self.is_dirty = self.is_dirty || action.is_edit();
-                self.content.perform(action)
+            Message::FadeToast => {
+                if matches!(self.toast, Some(Toast::Done(_, at)) if at.elapsed() >= TOAST_DURATION)
+                {
+                    self.toast = None;
+                }
             }
-            Message::Open => {
-                return Command::perform(pick_file(), Message::FileOpened);
+            Message::DismissToast => {
+                self.toast = None;
             }
-            Message::FileOpened(Ok((path, contents))) => {
-                self.path = Some(path);
-                self.error = None;
-                self.is_dirty = false;
-                self.content = text_editor::Content::with_text(&contents);
+            // The text editor scrolls 4 lines a wheel notch
+            Message::Edit(text_editor::Action::Scroll { lines }) => {
+                let lines =
+                    lines as f32 * self.config.wheel_scroll_lines / 4.0 + self.scroll_remainder;
+
+                self.scroll_remainder = lines.fract();
+
+                if self.config.smooth_scroll && !self.reduce_motion {
+                    self.pending_scroll += lines as i32;
+                } else {
+                    self.content.perform(text_editor::Action::Scroll {
+                        lines: lines as i32,
+                    });
+                }
             }
-            Message::FileOpened(Err(err)) => {
-                self.error = Some(err);
+            Message::SmoothScroll => {
+                // A quarter of the way there each frame, easing out
+                let step = (self.pending_scroll.abs() + 3) / 4 * self.pending_scroll.signum();
+
+                self.pending_scroll -= step;
+                self.content
+                    .perform(text_editor::Action::Scroll { lines: step });
             }
-            Message::ThemeSelected(theme) => {
-                self.theme = theme;
+            Message::ReduceMotion(reduce_motion) => {
+                self.reduce_motion = reduce_motion;
             }
-        }
+            // Backspace at the start and Delete at the end change nothing, so
+            // they shouldn't mark the buffer dirty or run the edit follow-ups
+            Message::Edit(text_editor::Action::Edit(edit))
+                if cursor::is_at_edge(&self.content, &edit) => {}
+            Message::Edit(action) => {
+                if action.is_edit() && self.is_read_only {
+                    // Typing into a file that can't be written is when to say
+                    // so, with the way out
+                    if let Some(path) = self.path.clone().filter(|_| self.is_write_protected) {
+                        self.error = Some(EditorError::PermissionDenied(path));
+                    }
 
-        Command::none()
-    }
+                    return Command::none();
+                }
 
-    fn subscription(&self) -> Subscription<Self::Message> {
-        keyboard::on_key_press(|key, modifiers| match key.as_ref() {
-            keyboard::Key::Character("s") if modifiers.command() => Some(Message::Save),
-            _ => None,
-        })
-    }
+                self.is_dirty = self.is_dirty || action.is_edit();
 
-    fn view(&self) -> Element<'_, Self::Message> {
-        let controls = row![
-            action(new_icon(), "New file", Some(Message::New)),
-            action(open_icon(), "Open file", Some(Message::Open)),
-            action(
-                save_icon(),
-                "Save file",
-                self.is_dirty.then_some(Message::Save)
-            ),
-            horizontal_space(),
-            pick_list(
-                highlighter::Theme::ALL,
-                Some(self.theme),
-                Message::ThemeSelected
-            )
-        ]
-        .spacing(10);
+                if action.is_edit() {
+                    self.last_edit = Some(Instant::now());
+                    self.is_offering_templates = false;
+                }
 
-        let input = container(
-            text_editor(&self.content)
-                .on_action(Message::Edit)
-                .highlight::<Highlighter>(
-                    highlighter::Settings {
-                        theme: self.theme,
-                        extension: self
-                            .path
-                            .as_ref()
-                            .and_then(|p| p.extension().map(|e| e.to_str()))
-                            .flatten()
-                            .unwrap_or("rs")
-                            .to_string(),
-                    },
-                    |highlight, _| highlight.to_format(),
-                )
-                .height(iced::Length::Fill),
-        )
-        .padding(10);
+                // Cuts come through as plain deletes, so they're picked up from
+                // the clipboard once the Ctrl+X key press is seen instead
+                if let text_editor::Action::Edit(text_editor::Edit::Paste(contents)) = &action {
+                    self.remember_clipboard(contents.to_string());
+                }
 
-        let status_bar = {
-            let status = if let Some(error) = &self.error {
-                text(error.to_string())
-            } else {
-                match self.path.as_deref().and_then(Path::to_str) {
-                    Some(path) => text(path).size(14),
-                    None => text("New file"),
+                // Ctrl+Shift+V pastes the clipboard as plain text
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Paste(contents))
+                        if self.modifiers.shift() =>
+                    {
+                        text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+                            transform::plain_text(&contents),
+                        )))
+                    }
+                    action => action,
+                };
+
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Paste(contents))
+                        if self.config.reindent_paste && contents.contains('\n') =>
+                    {
+                        let (line, column) = self.content.cursor_position();
+                        let before = self
+                            .content
+                            .line(line)
+                            .map(|text| text.get(..column).unwrap_or(&text).to_string())
+                            .unwrap_or_default();
+
+                        text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(
+                            format::reindent_paste(
+                                &contents,
+                                &before,
+                                self.config.indent_style,
+                                self.config.indent_width,
+                            ),
+                        )))
+                    }
+                    action => action,
+                };
+
+                // An opening bracket or quote gets its closing one, which
+                // typing it again then steps over
+                let mut closing = None;
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Insert(c))
+                        if self.config.auto_close && self.content.selection().is_none() =>
+                    {
+                        let (line, column) = self.content.cursor_position();
+                        let text = self
+                            .content
+                            .line(line)
+                            .map(|text| text.to_string())
+                            .unwrap_or_default();
+                        let (before, after) = text.split_at(column.min(text.len()));
+
+                        match brackets::typed(
+                            &self.bracket_pairs(),
+                            c,
+                            before.chars().next_back(),
+                            after.chars().next(),
+                        ) {
+                            brackets::Typed::Over => {
+                                text_editor::Action::Move(text_editor::Motion::Right)
+                            }
+                            brackets::Typed::Close(close) => {
+                                closing = Some(close);
+                                action
+                            }
+                            brackets::Typed::Plain => action,
+                        }
+                    }
+                    action => action,
+                };
+
+                match action {
+                    text_editor::Action::Click(point) => {
+                        self.drag = Some(Drag {
+                            pointer: None,
+                            last: point,
+                            origin: None,
+                            bottom: point.y,
+                        });
+                    }
+                    text_editor::Action::Drag(point) => {
+                        if let Some(drag) = &mut self.drag {
+                            drag.origin = drag
+                                .pointer
+                                .map(|pointer| pointer - Vector::new(point.x, point.y));
+                            drag.last = point;
+                            drag.bottom = drag.bottom.max(point.y);
+                        }
+                    }
+                    _ => {}
                 }
-            };
 
-            let position = {
-                let (row, col) = self.content.cursor_position();
+                let cursor = self.content.cursor_position();
+                let line_count = self.content.line_count();
+                let is_edit = action.is_edit();
+                let is_enter =
+                    matches!(action, text_editor::Action::Edit(text_editor::Edit::Enter));
 
-                format!("{}:{}", row + 1, col + 1)
-            };
+                self.content.perform(action);
 
-            row![status, horizontal_space(), Text::new(position)]
-        };
+                if let Some(close) = closing {
+                    self.content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Insert(close)));
+                    self.content
+                        .perform(text_editor::Action::Move(text_editor::Motion::Left));
+                }
 
-        container(column![controls, input, status_bar])
-            .padding(10)
-            .into()
-    }
+                if is_edit {
+                    self.shift_notes(cursor.0, line_count);
+                }
 
-    fn theme(&self) -> iced::Theme {
-        Theme::Dark
-    }
-}
+                // The line Enter leaves is done with, cursor or not
+                if is_enter && self.config.trim_trailing_on_enter {
+                    self.trim_trailing(cursor.0);
+                }
 
-async fn pick_file() -> Result<(PathBuf, Arc<String>), EditorError> {
-    let handle = AsyncFileDialog::new()
-        .pick_file()
-        .await
-        .ok_or(EditorError::PickFileError)?;
+                // The new line is indented like the one Enter was pressed on,
+                // give or take a level by the rules of the language
+                let rules = self
+                    .config
+                    .indent_rules
+                    .get(language::name(&self.language()));
+                let indentation = self
+                    .content
+                    .line(cursor.0)
+                    .filter(|_| is_enter && self.config.auto_indent)
+                    .map(|line| {
+                        let end = line.len() - line.trim_start().len();
+                        let indentation = &line[..end.min(cursor.1)];
 
-    load_file(handle.path().to_owned()).await
-}
+                        match rules {
+                            Some(rules) => rules.next_indentation(
+                                &line,
+                                indentation,
+                                self.config.indent_style,
+                                self.config.indent_width,
+                            ),
+                            None => indentation.to_string(),
+                        }
+                    })
+                    .unwrap_or_default();
 
-async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), EditorError> {
-    let contents = fs::read_to_string(&path).await?.into();
-    Ok((path, contents))
-}
+                // Inside a comment, the new line carries it on instead
+                let indentation = self
+                    .content
+                    .line(cursor.0)
+                    .filter(|_| is_enter && self.config.continue_comments)
+                    .and_then(|line| {
+                        let language = self.language();
 
-async fn save_file(path: Option<PathBuf>, contents: String) -> Result<PathBuf, EditorError> {
-    let path = match path {
-        Some(path) => path,
-        None => AsyncFileDialog::new()
-            .set_title("Choose a name...")
-            .save_file()
-            .await
-            .ok_or(EditorError::PickFileError)?
-            .path()
-            .to_owned(),
-    };
+                        format::comment_continuation(
+                            line.get(..cursor.1).unwrap_or(&line),
+                            language::line_comment(&language),
+                            language::block_comment(&language),
+                        )
+                    })
+                    .unwrap_or(indentation);
 
-    fs::write(&path, contents).await?;
+                if let Some(line) = self.auto_indented {
+                    // Trimming would drop a selection being made
+                    if self.content.cursor_position().0 != line
+                        && self.content.selection().is_none()
+                    {
+                        self.auto_indented = None;
 
-    Ok(path)
-}
+                        if self.config.trim_auto_indent {
+                            self.trim_line(line);
+                        }
+                    } else if is_edit {
+                        self.auto_indented = None;
+                    }
+                }
 
-fn default_file() -> PathBuf {
-    PathBuf::from(format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")))
-}
+                if !indentation.is_empty() {
+                    self.content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                            Arc::new(indentation),
+                        )));
+                    self.auto_indented = Some(self.content.cursor_position().0);
+                }
 
-fn action<'a>(
-    content: Element<'a, Message>,
-    label: &'a str,
-    press: Option<Message>,
-) -> Element<'a, Message> {
-    let is_disabled = press.is_none();
+                if is_edit {
+                    self.check_indentation();
+                }
 
-    tooltip(
-        button(container(content).width(30).center_x())
-            .on_press_maybe(press)
-            .padding([5, 10])
-            .style(if is_disabled {
-                theme::Button::Secondary
-            } else {
-                theme::Button::Primary
-            }),
-        label,
-        tooltip::Position::FollowCursor,
-    )
-    .style(theme::Container::Box)
-    .into()
+                if self.content.cursor_position() != cursor {
+                    self.occurrence = None;
+                    self.cursor_moved_at = Some(Instant::now());
+
+                    if self.config.occurrence_delay_ms == 0 {
+                        self.highlight_occurrences();
+                    }
+                }
+            }
+            Message::HighlightOccurrences => {
+                let delay = Duration::from_millis(self.config.occurrence_delay_ms);
+
+                if self
+                    .cursor_moved_at
+                    .is_some_and(|moved_at| moved_at.elapsed() >= delay)
+                {
+                    self.highlight_occurrences();
+                }
+            }
+            Message::Open => {
+                return Command::perform(
+                    pick_files(None, self.config.read_timeout()),
+                    Message::FilesOpened,
+                );
+            }
+            Message::FileOpened(Ok((path, contents))) => {
+                self.path = Some(path);
+                self.theme_override = None;
+                self.tail = None;
+                self.file_len = Some(contents.len() as u64);
+                self.following = None;
+                self.had_final_newline = contents.is_empty() || contents.ends_with('\n');
+                self.is_highlighting_paused = contents
+                    .lines()
+                    .any(|line| line.len() > self.config.long_line_threshold);
+                self.error = None;
+                self.language = None;
+                self.is_offering_templates = false;
+                self.note_draft = None;
+                self.tag_draft = None;
+                self.is_dirty = false;
+                self.line_ending = LineEnding::detect(&contents).unwrap_or_default();
+                self.is_read_only = std::mem::take(&mut self.pending_read_only);
+                self.is_write_protected = false;
+                self.last_save = None;
+                self.auto_indented = None;
+                self.occurrence = None;
+                *self.recovery.lock().expect("Lock recovery snapshot") = None;
+                self.content =
+                    text_editor::Content::with_text(&format::normalize_line_endings(&contents));
+
+                self.check_indentation();
+
+                let invisibles = transform::find_invisible(&contents);
+                self.invisibles = (!invisibles.is_empty()).then_some(invisibles);
+
+                if let Some((line, column)) = self.pending_goto.take() {
+                    let column = cursor::byte_column(&self.content, line, column);
+
+                    cursor::move_to(&mut self.content, line, column);
+                }
+
+                self.notes = self
+                    .path
+                    .as_deref()
+                    .and_then(|path| self.config.notes.get(&absolute_path(path)))
+                    .cloned()
+                    .unwrap_or_default();
+
+                if let Some(path) = &self.path {
+                    self.config.remember_recent_file(absolute_path(path));
+
+                    return Command::batch([
+                        self.detect_root(),
+                        Command::perform(is_writable(path.clone()), Message::WritabilityChecked),
+                        Command::perform(
+                            config::save_config(self.config.clone()),
+                            Message::ConfigSaved,
+                        ),
+                        Command::perform(recovery::check_in(self.session(), None), |_| {
+                            Message::CheckedIn
+                        }),
+                    ]);
+                }
+            }
+            Message::WritabilityChecked((path, is_writable)) => {
+                if !is_writable && self.path.as_ref() == Some(&path) {
+                    self.is_read_only = true;
+                    self.is_write_protected = true;
+                }
+            }
+            Message::FilesOpened(Ok(results)) => {
+                let (opened, failed): (Vec<_>, Vec<_>) =
+                    results.into_iter().partition(Result::is_ok);
+                let mut opened = opened.into_iter().flatten();
+                let failed: Vec<_> = failed.into_iter().filter_map(Result::err).collect();
+
+                // Without tabs, the rest wait their turn after the first one
+                let command = opened
+                    .next()
+                    .map(|file| self.update(Message::FileOpened(Ok(file))))
+                    .unwrap_or_else(Command::none);
+
+                self.queued_files = opened.collect();
+
+                if !failed.is_empty() {
+                    self.error = Some(EditorError::LoadFailed(failed));
+                }
+
+                return command;
+            }
+            Message::FilesOpened(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::Launched(target) => {
+                let has_unsaved_work = self.is_dirty && !self.content.text().trim().is_empty();
+
+                // Without tabs, the file waits its turn rather than replace
+                // unsaved work
+                let open = if has_unsaved_work {
+                    Command::perform(
+                        load_file(target.path, self.config.read_timeout()),
+                        Message::FileQueued,
+                    )
+                } else {
+                    self.pending_goto = target.position();
+
+                    Command::perform(
+                        load_file(target.path, self.config.read_timeout()),
+                        Message::FileOpened,
+                    )
+                };
+
+                return Command::batch([open, window::gain_focus(window::Id::MAIN)]);
+            }
+            Message::AutomationFailed(err) => {
+                self.error = Some(EditorError::AutomationFailed(err));
+            }
+            Message::Automation(request, reply) => {
+                let (response, command) = self.automate(request);
+                let _ = reply.send(response);
+
+                return command;
+            }
+            Message::FileQueued(Ok(file)) => {
+                self.queued_files.push(file);
+            }
+            Message::FileQueued(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::OpenNext => {
+                if !self.queued_files.is_empty() {
+                    let file = self.queued_files.remove(0);
+
+                    return self.update(Message::FileOpened(Ok(file)));
+                }
+            }
+            Message::FileOpened(Err(EditorError::IsDirectory(path)))
+                if self.config.open_folder == config::OpenFolder::Pick && !self.wait =>
+            {
+                self.pending_goto = None;
+                self.pending_read_only = false;
+
+                return Command::perform(
+                    pick_files(Some(path), self.config.read_timeout()),
+                    Message::FilesOpened,
+                );
+            }
+            Message::FileOpened(Err(err)) => {
+                self.pending_goto = None;
+                self.pending_read_only = false;
+                self.error = Some(err);
+            }
+            Message::ThemeSelected(theme) if self.theme_override.is_some() => {
+                self.theme_override = Some(theme);
+            }
+            Message::ThemeSelected(theme) => {
+                self.theme = theme;
+                self.is_theme_pinned = false;
+                self.config.theme_mode = config::ThemeMode::Fixed(theme.to_string());
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::AppearanceChanged(appearance) => {
+                self.appearance = Some(appearance);
+
+                if !self.is_theme_pinned {
+                    self.theme = self.config.theme_mode.theme(self.appearance);
+                }
+            }
+            Message::ResumeHighlighting => {
+                self.is_highlighting_paused = false;
+            }
+            Message::ToggleThemeOverride => {
+                self.theme_override = match self.theme_override {
+                    Some(_) => None,
+                    None => Some(self.theme),
+                };
+            }
+            Message::UseSystemTheme => {
+                self.is_theme_pinned = false;
+                self.config.theme_mode = config::ThemeMode::System;
+                self.theme = self.config.theme_mode.theme(self.appearance);
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::ToggleAlwaysOnTop => {
+                self.config.always_on_top = !self.config.always_on_top;
+
+                return Command::batch([
+                    window::change_level(window::Id::MAIN, window_level(&self.config)),
+                    Command::perform(
+                        config::save_config(self.config.clone()),
+                        Message::ConfigSaved,
+                    ),
+                ]);
+            }
+            // Dragging the slider only saves once it's let go
+            Message::OpacityChanged(opacity) => {
+                self.config.opacity = opacity.clamp(config::MIN_OPACITY, 1.0);
+                self.is_showing_opacity |= self.config.opacity() < 1.0;
+            }
+            Message::SetOpacity(opacity) => {
+                self.config.opacity = opacity.clamp(config::MIN_OPACITY, 1.0);
+                self.is_showing_opacity |= self.config.opacity() < 1.0;
+
+                return self.update(Message::SaveOpacity);
+            }
+            Message::SaveOpacity => {
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::LanguageSelected(language) => {
+                self.language = Some(language.clone());
+
+                let Some(path) = self
+                    .path
+                    .as_deref()
+                    .filter(|path| !has_known_extension(path))
+                else {
+                    return Command::none();
+                };
+
+                self.config
+                    .language_overrides
+                    .insert(absolute_path(path), language);
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::FontSelected(family) => {
+                self.font = fonts::font(fonts::families(&self.config.fonts), &family);
+                self.config.font = (family != fonts::DEFAULT).then_some(family);
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::ConfigSaved(Ok(())) => {}
+            Message::ConfigSaved(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::PointerMoved(position) => {
+                if let Some(drag) = &mut self.drag {
+                    drag.pointer = Some(position);
+                }
+            }
+            Message::PointerReleased => {
+                self.drag = None;
+            }
+            Message::DragScroll => {
+                let Some(Drag {
+                    pointer: Some(pointer),
+                    last,
+                    origin: Some(origin),
+                    bottom,
+                }) = self.drag
+                else {
+                    return Command::none();
+                };
+
+                let y = pointer.y - origin.y;
+                let distance = if y < 0.0 { y } else { (y - bottom).max(0.0) };
+
+                if distance == 0.0 {
+                    return Command::none();
+                }
+
+                // The further past the edge, the faster it goes
+                let lines = (distance / 20.0 * self.config.drag_scroll_speed).round() as i32;
+                let lines = if lines == 0 {
+                    distance.signum() as i32
+                } else {
+                    lines
+                };
+
+                self.content.perform(text_editor::Action::Scroll { lines });
+                self.content.perform(text_editor::Action::Drag(Point::new(
+                    last.x,
+                    y.clamp(0.0, bottom),
+                )));
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            Message::GoTo(line, column) => {
+                cursor::move_to(&mut self.content, line, column);
+            }
+            Message::FindInvisibles => {
+                self.invisibles = Some(transform::find_invisible(&self.buffer_text()));
+            }
+            Message::StripInvisibles if self.is_read_only => {}
+            Message::StripInvisibles => {
+                let text = transform::strip_invisible(&self.buffer_text());
+
+                self.replace_text(&text);
+                self.invisibles = None;
+            }
+            Message::CloseInvisibles => {
+                self.invisibles = None;
+            }
+            Message::ToggleLineEnding if self.is_read_only => {}
+            Message::ToggleLineEnding => {
+                self.line_ending = self.line_ending.toggle();
+                self.is_dirty = true;
+            }
+            Message::CycleIndentation => {
+                (self.config.indent_style, self.config.indent_width) =
+                    format::next_indentation(self.config.indent_style, self.config.indent_width);
+                self.check_indentation();
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::CompareFiles => {
+                return Command::perform(
+                    pick_comparison(self.config.read_timeout()),
+                    Message::FilesCompared,
+                );
+            }
+            Message::FilesCompared(Ok(comparison)) => {
+                self.comparison = Some(comparison);
+            }
+            Message::FilesCompared(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::PreviousHunk | Message::NextHunk => {
+                let forward = matches!(message, Message::NextHunk);
+
+                if let Some(row) = self
+                    .comparison
+                    .as_mut()
+                    .and_then(|comparison| comparison.step(forward))
+                {
+                    return scrollable::scroll_to(
+                        comparison_scrollable(),
+                        scrollable::AbsoluteOffset {
+                            x: 0.0,
+                            y: row as f32 * DIFF_ROW_HEIGHT,
+                        },
+                    );
+                }
+            }
+            Message::CloseComparison => {
+                self.comparison = None;
+            }
+            Message::ToggleIgnoreWhitespace => {
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.toggle_ignore_whitespace();
+                }
+            }
+            // The buffer of a tail or a followed file only mirrors the file
+            Message::ToggleReadOnly if self.tail.is_some() || self.following.is_some() => {}
+            // A dirty read-only buffer would hold changes that can't be
+            // touched yet still need saving
+            Message::ToggleReadOnly if !self.is_read_only && self.is_dirty => {
+                self.error = Some(EditorError::UnsavedChanges);
+            }
+            Message::ToggleReadOnly => {
+                self.is_read_only = !self.is_read_only;
+                self.error = None;
+            }
+            Message::AutoSave(trigger) => {
+                let is_due = match trigger {
+                    config::AutoSaveTrigger::Idle => {
+                        let idle = Duration::from_millis(self.config.auto_save_idle_ms);
+
+                        self.last_edit.is_some_and(|at| at.elapsed() >= idle)
+                    }
+                    config::AutoSaveTrigger::OnFocusLost => true,
+                };
+
+                // Untitled buffers would pop up a file dialog out of nowhere
+                if is_due
+                    && self.is_dirty
+                    && self.path.is_some()
+                    && self.config.auto_save.contains(&trigger)
+                {
+                    self.last_edit = None;
+
+                    return self.update(Message::Save);
+                }
+            }
+            Message::CloseRequested => {
+                if !self.is_dirty {
+                    return self.exit();
+                }
+
+                self.is_confirming_close = true;
+            }
+            Message::SaveAndClose => {
+                self.is_confirming_close = false;
+                self.close_after_save = true;
+
+                return self.update(Message::Save);
+            }
+            Message::DiscardAndClose => {
+                return self.exit();
+            }
+            Message::CancelClose => {
+                self.is_confirming_close = false;
+            }
+            Message::Exit => {
+                recovery::mark_exited();
+
+                return window::close(window::Id::MAIN);
+            }
+            Message::SnapshotBuffer => {
+                let snapshot = recovery::Snapshot {
+                    path: self.path.clone(),
+                    text: self.content.text(),
+                };
+
+                *self.recovery.lock().expect("Lock recovery snapshot") = Some(snapshot.clone());
+
+                return Command::perform(
+                    recovery::check_in(self.session(), Some(snapshot)),
+                    |_| Message::CheckedIn,
+                );
+            }
+            Message::CheckedIn => {}
+            Message::ReopenCrashed => {
+                if let Some(session) = self.crashed_session.take() {
+                    self.pending_goto = Some((session.line, session.column));
+
+                    return Command::perform(
+                        load_file(session.path, self.config.read_timeout()),
+                        Message::FileOpened,
+                    );
+                }
+            }
+            Message::DismissCrashed => {
+                self.crashed_session = None;
+            }
+            Message::RestoreRecovered => {
+                if let Some(snapshot) = self.recovered.take() {
+                    self.path = snapshot.path;
+                    self.error = None;
+                    self.language = None;
+                    self.invisibles = None;
+                    if let Some(hidden) = &mut self.hidden_panels {
+                        hidden.invisibles = None;
+                    }
+                    self.is_read_only = false;
+                    self.is_write_protected = false;
+                    self.is_dirty = true;
+                    self.content = text_editor::Content::with_text(&snapshot.text);
+                    self.check_indentation();
+                }
+
+                recovery::discard();
+            }
+            Message::DiscardRecovered => {
+                self.recovered = None;
+
+                recovery::discard();
+            }
+            Message::OpenPalette => {
+                self.palette = Some(String::new());
+
+                return text_input::focus(palette_input());
+            }
+            Message::PaletteChanged(query) => {
+                self.palette = Some(query);
+            }
+            Message::SubmitPalette => {
+                let query = self.palette.clone().unwrap_or_default();
+
+                let command = self
+                    .commands()
+                    .into_iter()
+                    .filter(|(label, _)| palette::matches(&query, label))
+                    .find_map(|(_, command)| command);
+
+                if let Some(command) = command {
+                    return self.update(Message::RunCommand(Box::new(command)));
+                }
+            }
+            Message::RunCommand(command) => {
+                self.palette = None;
+                self.is_path_menu_open = false;
+
+                return self.update(*command);
+            }
+            Message::OpenFind => {
+                // A selection within a line is what's usually looked for
+                let query = self
+                    .content
+                    .selection()
+                    .filter(|selection| !selection.contains('\n'))
+                    .or_else(|| self.find.take().map(|find| find.query))
+                    .unwrap_or_default();
+
+                self.find = Some(Find {
+                    query,
+                    ..Find::default()
+                });
+
+                return Command::batch([
+                    text_input::focus(find_input()),
+                    text_input::select_all(find_input()),
+                ]);
+            }
+            Message::FindChanged(query) => {
+                self.find = Some(Find {
+                    query,
+                    ..Find::default()
+                });
+            }
+            Message::SubmitFind => {
+                return self.update(if self.modifiers.shift() {
+                    Message::FindPrevious
+                } else {
+                    Message::FindNext
+                });
+            }
+            Message::FindNext | Message::FindPrevious => {
+                let Some(query) = self.find.as_ref().map(|find| find.query.clone()) else {
+                    return self.update(Message::OpenFind);
+                };
+
+                let direction = match message {
+                    Message::FindPrevious => search::Direction::Backward,
+                    _ => search::Direction::Forward,
+                };
+
+                // Looking back starts ahead of the match already selected
+                let (line, column) = self.content.cursor_position();
+                let column = match direction {
+                    search::Direction::Forward => column,
+                    search::Direction::Backward => self
+                        .content
+                        .selection()
+                        .filter(|selection| !selection.contains('\n'))
+                        .map_or(column, |selection| column.saturating_sub(selection.len())),
+                };
+
+                let found = search::find(&self.buffer_text(), &query, (line, column), direction);
+
+                if let Some((found, _)) = &found {
+                    cursor::select(&mut self.content, found.line, found.range.clone());
+                }
+
+                if let Some(find) = &mut self.find {
+                    find.has_no_matches = found.is_none();
+                    find.current = found
+                        .as_ref()
+                        .map(|(found, _)| (found.line, found.range.start));
+                    find.wrapped = found
+                        .filter(|&(_, wrapped)| wrapped)
+                        .map(|_| (direction, Instant::now()));
+                }
+
+                if !query.is_empty() {
+                    self.config.remember_search(query);
+                }
+
+                // Remembering moved the query to the front of the history
+                if let Some(find) = &mut self.find {
+                    find.recalled = None;
+                }
+            }
+            Message::ExpireFindNotice => {
+                if let Some(find) = &mut self.find {
+                    if find
+                        .wrapped
+                        .is_some_and(|(_, at)| at.elapsed() >= TOAST_DURATION)
+                    {
+                        find.wrapped = None;
+                    }
+                }
+            }
+            Message::CloseFind => {
+                self.find = None;
+            }
+            // The arrows only reach here from the find bar, or with nothing
+            // focused, since the other inputs also leave them be
+            Message::RecallSearch { .. }
+                if self.palette.is_some()
+                    || self.symbol_query.is_some()
+                    || self.note_draft.is_some()
+                    || self.tag_draft.is_some() => {}
+            Message::RecallSearch { older } => {
+                let history = &self.config.search_history;
+                let Some(find) = self.find.as_mut().filter(|_| !history.is_empty()) else {
+                    return Command::none();
+                };
+
+                let recalled = match find.recalled.take() {
+                    None if older => Some((0, find.query.clone())),
+                    None => None,
+                    Some((index, typed)) if older => {
+                        Some(((index + 1).min(history.len() - 1), typed))
+                    }
+                    Some((0, typed)) => {
+                        find.query = typed;
+                        None
+                    }
+                    Some((index, typed)) => Some((index - 1, typed)),
+                };
+
+                if let Some((index, _)) = &recalled {
+                    find.query = history[*index].clone();
+                    find.has_no_matches = false;
+                    find.current = None;
+                }
+                find.recalled = recalled;
+
+                return text_input::move_cursor_to_end(find_input());
+            }
+            Message::OpenSymbols => {
+                self.symbol_query = Some(String::new());
+
+                return text_input::focus(symbol_input());
+            }
+            Message::SymbolQueryChanged(query) => {
+                self.symbol_query = Some(query);
+            }
+            Message::SubmitSymbols => {
+                if let Some(symbol) = self.matching_symbols().first() {
+                    return self.update(Message::JumpToSymbol(symbol.line));
+                }
+            }
+            // Copies are done by the text editor without telling, so the
+            // clipboard is read back after the shortcut instead
+            Message::ClipboardChanged => {
+                return clipboard::read(Message::ClipboardRead);
+            }
+            Message::ClipboardRead(Some(contents)) => {
+                self.remember_clipboard(contents);
+            }
+            Message::ClipboardRead(None) => {}
+            Message::OpenPasteHistory => {
+                self.is_picking_paste = !self.clipboard_ring.is_empty();
+            }
+            Message::PasteFromHistory(_) if self.is_read_only => {}
+            Message::PasteFromHistory(index) => {
+                self.is_picking_paste = false;
+
+                if index < self.clipboard_ring.len() {
+                    let contents = self.clipboard_ring.remove(index);
+
+                    self.clipboard_ring.insert(0, contents.clone());
+                    self.replace_selection(contents);
+                }
+            }
+            Message::WrapSelection(_) | Message::SubmitTag if self.is_read_only => {}
+            Message::WrapSelection(brackets::Pair::Chars(open, close)) => {
+                self.wrap_selection(&open.to_string(), &close.to_string());
+            }
+            Message::WrapSelection(brackets::Pair::Tag) => {
+                self.tag_draft = Some(String::new());
+
+                return text_input::focus(tag_input());
+            }
+            Message::TagChanged(draft) => {
+                self.tag_draft = Some(draft);
+            }
+            Message::SubmitTag => {
+                // Attributes stay in the opening tag only
+                let tag = self.tag_draft.take().unwrap_or_default();
+                let name = tag.split_whitespace().next().unwrap_or_default();
+
+                if !name.is_empty() {
+                    self.wrap_selection(&format!("<{}>", tag.trim()), &format!("</{name}>"));
+                }
+            }
+            Message::JumpToMatchingBracket => {
+                let text = self.buffer_text();
+                let lines: Vec<&str> = text.split('\n').collect();
+                let matching = brackets::matching(
+                    &lines,
+                    self.content.cursor_position(),
+                    &self.bracket_pairs(),
+                );
+
+                if let Some((line, column)) = matching {
+                    cursor::move_to(&mut self.content, line, column);
+                }
+            }
+            Message::PeekDefinition => {
+                let (line, column) = self.content.cursor_position();
+                let name = self
+                    .content
+                    .line(line)
+                    .and_then(|line| highlight::word_at(&line, column).map(String::from));
+
+                self.peek = name.map(|name| {
+                    let lines = symbols::definitions(&self.buffer_text(), &self.language(), &name);
+
+                    (name, lines)
+                });
+            }
+            Message::ClosePeek => {
+                self.peek = None;
+            }
+            Message::JumpToSymbol(line) => {
+                self.symbol_query = None;
+                self.peek = None;
+                cursor::move_to(&mut self.content, line, 0);
+            }
+            Message::ToggleOutline => {
+                self.outline = match self.outline {
+                    Some(_) => None,
+                    None => Some(Vec::new()),
+                };
+                self.outline_hash = 0;
+                self.hidden_panels = None;
+                self.config.show_outline = self.outline.is_some();
+
+                return Command::batch([
+                    self.update(Message::RefreshOutline),
+                    Command::perform(
+                        config::save_config(self.config.clone()),
+                        Message::ConfigSaved,
+                    ),
+                ]);
+            }
+            Message::ToggleAllPanels => match self.hidden_panels.take() {
+                // Anything opened since is kept, rather than replaced
+                Some(hidden) => {
+                    if hidden.outline && self.outline.is_none() {
+                        self.outline = Some(Vec::new());
+                        self.outline_hash = 0;
+                    }
+                    self.peek = self.peek.take().or(hidden.peek);
+                    self.invisibles = self.invisibles.take().or(hidden.invisibles);
+                    self.checksum = self.checksum.take().or(hidden.checksum);
+                    self.comparison = self.comparison.take().or(hidden.comparison);
+
+                    return self.update(Message::RefreshOutline);
+                }
+                None => {
+                    let hidden = Panels {
+                        outline: self.outline.take().is_some(),
+                        peek: self.peek.take(),
+                        invisibles: self.invisibles.take(),
+                        checksum: self.checksum.take(),
+                        comparison: self.comparison.take(),
+                    };
+
+                    if hidden.is_empty() {
+                        self.toast = Some(Toast::done("No panels are open"));
+                    } else {
+                        self.hidden_panels = Some(hidden);
+                    }
+                }
+            },
+            Message::RefreshOutline => {
+                let text = self.buffer_text();
+                let hash = content_hash(&text);
+
+                if self.outline.is_some() && hash != self.outline_hash {
+                    self.outline = Some(symbols::find(&text, &self.language()));
+                    self.outline_hash = hash;
+                }
+            }
+            Message::Escape => {
+                let order = self.config.escape_order.clone();
+
+                for step in order {
+                    if self.back_out(step) {
+                        break;
+                    }
+                }
+            }
+            Message::TogglePathMenu => {
+                self.is_path_menu_open = !self.is_path_menu_open && self.path.is_some();
+            }
+            Message::CopyPath => {
+                if let Some(path) = self.path.as_deref().and_then(Path::to_str) {
+                    return clipboard::write(path.to_string());
+                }
+            }
+            Message::CopyFileName => {
+                let file_name = self
+                    .path
+                    .as_deref()
+                    .and_then(Path::file_name)
+                    .and_then(|name| name.to_str());
+
+                if let Some(name) = file_name {
+                    return clipboard::write(name.to_string());
+                }
+            }
+            Message::OpenTail => {
+                return Command::perform(
+                    pick_tail(self.config.tail_kb * 1024),
+                    Message::TailOpened,
+                );
+            }
+            Message::TailOpened(Ok((path, chunk))) => {
+                self.pending_read_only = true;
+
+                let command = self.update(Message::FileOpened(Ok((path, Arc::new(chunk.text)))));
+                self.tail = Some(chunk.start);
+                self.file_len = Some(chunk.end);
+
+                return command;
+            }
+            Message::TailOpened(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::LoadMore => {
+                if let (Some(path), Some(start)) = (self.path.clone(), self.tail) {
+                    let size = self.config.tail_kb * 1024;
+
+                    return Command::perform(
+                        async move { tail::read_before(&path, Some(start), size).await },
+                        Message::MoreLoaded,
+                    );
+                }
+            }
+            Message::MoreLoaded(Ok(chunk)) => {
+                let (line, column) = self.content.cursor_position();
+                let added = chunk.text.matches('\n').count();
+
+                self.content = text_editor::Content::with_text(&(chunk.text + &self.buffer_text()));
+                self.tail = Some(chunk.start);
+
+                cursor::move_to(&mut self.content, line + added, column);
+            }
+            Message::MoreLoaded(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::ToggleFollow => match self.following.take() {
+                Some(was_read_only) => self.is_read_only = was_read_only,
+                None if self.file_len.is_some() && !self.is_dirty => {
+                    self.following = Some(self.is_read_only);
+                    self.is_read_only = true;
+                }
+                None => {}
+            },
+            Message::CheckFollowed => {
+                if let (Some(path), Some(start)) = (self.path.clone(), self.file_len) {
+                    return Command::perform(
+                        async move { tail::read_after(&path, start).await },
+                        Message::FollowedRead,
+                    );
+                }
+            }
+            Message::FollowedRead(Ok(Some(chunk))) if self.following.is_some() => {
+                // The file shrank, so whatever it holds now replaces the buffer
+                if self.file_len.is_some_and(|len| chunk.start < len) {
+                    self.content = text_editor::Content::with_text(&chunk.text);
+                    self.tail = None;
+                } else {
+                    self.content
+                        .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                    self.content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                            Arc::new(chunk.text),
+                        )));
+                }
+
+                self.content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                self.file_len = Some(chunk.end);
+            }
+            Message::FollowedRead(Ok(_)) => {}
+            Message::FollowedRead(Err(err)) => {
+                if let Some(was_read_only) = self.following.take() {
+                    self.is_read_only = was_read_only;
+                }
+
+                self.error = Some(err);
+            }
+            Message::NormalizeIndentation if self.is_read_only => {}
+            Message::NormalizeIndentation => {
+                let text = format::normalize_indentation(
+                    &self.buffer_text(),
+                    self.config.indent_style,
+                    self.config.indent_width,
+                );
+
+                self.replace_text(&text);
+            }
+            Message::ConvertIndentation(_) if self.is_read_only => {}
+            Message::ConvertIndentation(style) => {
+                let text = self.buffer_text();
+                let converted =
+                    format::normalize_indentation(&text, style, self.config.indent_width);
+                let changed = text
+                    .split('\n')
+                    .zip(converted.split('\n'))
+                    .filter(|(line, converted)| line != converted)
+                    .count();
+
+                if changed > 0 {
+                    self.replace_text(&converted);
+                }
+
+                // The setting follows, so new lines are indented the same way
+                self.config.indent_style = style;
+                self.check_indentation();
+
+                self.toast = Some(Toast::done(match changed {
+                    1 => String::from("Converted the indentation of 1 line"),
+                    n => format!("Converted the indentation of {n} lines"),
+                }));
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::OpenRecent(path) => {
+                return Command::perform(
+                    load_file(path, self.config.read_timeout()),
+                    Message::FileOpened,
+                );
+            }
+            Message::ClearRecentFiles => {
+                self.config.recent_files.clear();
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::ClearHistory => {
+                self.config.recent_files.clear();
+                self.config.search_history.clear();
+
+                return Command::perform(
+                    config::save_config(self.config.clone()),
+                    Message::ConfigSaved,
+                );
+            }
+            Message::ToggleBlockComment if self.is_read_only => {}
+            Message::ToggleBlockComment => {
+                let (Some(selection), Some(comment)) = (
+                    self.content.selection(),
+                    language::block_comment(&self.language()),
+                ) else {
+                    return Command::none();
+                };
+
+                match transform::toggle_block_comment(&selection, comment) {
+                    Ok(text) => self.replace_selection(text),
+                    Err(err) => self.error = Some(EditorError::TransformError(err.to_string())),
+                }
+            }
+            Message::TransposeChars | Message::TransposeWords if self.is_read_only => {}
+            Message::TransposeChars | Message::TransposeWords => {
+                let (line, column) = self.content.cursor_position();
+                let text = self.content.line(line).map(|text| text.to_string());
+
+                let transposed = text.and_then(|text| match message {
+                    Message::TransposeChars => transform::transpose_chars(&text, column),
+                    _ => transform::transpose_words(&text, column),
+                });
+
+                // The cursor ends up past the swap, ready for the next one
+                if let Some((range, swapped)) = transposed {
+                    cursor::select(&mut self.content, line, range);
+                    self.replace_selection(swapped);
+                }
+            }
+            Message::SortByLength { .. } | Message::ShuffleLines if self.is_read_only => {}
+            Message::SortByLength { .. } | Message::ShuffleLines => {
+                let reorder = |text: &str| match message {
+                    Message::SortByLength { longest_first } => {
+                        transform::sort_by_length(text, longest_first)
+                    }
+                    _ => transform::shuffle_lines(text, fastrand::u64(..)),
+                };
+
+                let count = match self.content.selection() {
+                    Some(selection) => {
+                        self.replace_selection(reorder(&selection));
+                        selection.split('\n').count()
+                    }
+                    None => {
+                        let text = self.buffer_text();
+
+                        self.replace_text(&reorder(&text));
+                        text.split('\n').count()
+                    }
+                };
+
+                self.toast = Some(Toast::done(match message {
+                    Message::SortByLength { .. } => format!("Sorted {count} lines by length"),
+                    _ => format!("Shuffled {count} lines"),
+                }));
+            }
+            Message::TrimTrailingWhitespace if self.is_read_only => {}
+            Message::TrimTrailingWhitespace => {
+                let text =
+                    format::trim_trailing_whitespace(&self.buffer_text(), self.spared_line());
+
+                if text != self.buffer_text() {
+                    self.replace_text(&text);
+                }
+            }
+            Message::ReflowParagraph if self.is_read_only => {}
+            Message::ReflowParagraph => {
+                let width = self.config.reflow_column;
+
+                if let Some(selection) = self.content.selection() {
+                    self.replace_selection(transform::reflow(&selection, width));
+
+                    return Command::none();
+                }
+
+                let text = self.buffer_text();
+                let (line, _) = self.content.cursor_position();
+
+                let Some(paragraph) = transform::paragraph_at(&text, line) else {
+                    return Command::none();
+                };
+
+                let lines: Vec<&str> = text.split('\n').collect();
+                let reflowed = transform::reflow(&lines[paragraph.clone()].join("\n"), width);
+
+                let text = lines[..paragraph.start]
+                    .iter()
+                    .copied()
+                    .chain([reflowed.as_str()])
+                    .chain(lines[paragraph.end..].iter().copied())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                self.replace_text(&text);
+                cursor::move_to(&mut self.content, paragraph.start, 0);
+            }
+            Message::SqueezeBlankLines if self.is_read_only => {}
+            Message::SqueezeBlankLines => {
+                let removed = match self.content.selection() {
+                    Some(selection) => {
+                        let (text, removed) = transform::squeeze_blank_lines(&selection);
+
+                        if removed > 0 {
+                            self.replace_selection(text);
+                        }
+                        removed
+                    }
+                    None => {
+                        let (text, removed) = transform::squeeze_blank_lines(&self.buffer_text());
+
+                        if removed > 0 {
+                            self.replace_text(&text);
+                        }
+                        removed
+                    }
+                };
+
+                self.toast = Some(Toast::done(match removed {
+                    1 => String::from("Removed 1 blank line"),
+                    n => format!("Removed {n} blank lines"),
+                }));
+            }
+            Message::NormalizePunctuation if self.is_read_only => {}
+            Message::NormalizePunctuation => {
+                let punctuation = self.config.punctuation;
+
+                match self.content.selection() {
+                    Some(selection) => self.replace_selection(transform::normalize_punctuation(
+                        &selection,
+                        punctuation,
+                    )),
+                    None => {
+                        let text = self.buffer_text();
+                        let normalized = transform::normalize_punctuation(&text, punctuation);
+
+                        if normalized != text {
+                            self.replace_text(&normalized);
+                        }
+                    }
+                }
+            }
+            Message::EncodeSelection(_) | Message::DecodeSelection(_) if self.is_read_only => {}
+            Message::EncodeSelection(kind) => {
+                if let Some(selection) = self.content.selection() {
+                    self.replace_selection(transform::encode(&selection, kind));
+                }
+            }
+            Message::DecodeSelection(kind) => {
+                let Some(selection) = self.content.selection() else {
+                    return Command::none();
+                };
+
+                match transform::decode(&selection, kind) {
+                    Ok(text) => self.replace_selection(text),
+                    Err(err) => self.error = Some(EditorError::TransformError(err.to_string())),
+                }
+            }
+            Message::FormatJson | Message::MinifyJson if self.is_read_only => {}
+            Message::FormatJson | Message::MinifyJson => {
+                let selection = self.content.selection();
+                let text = selection.clone().unwrap_or_else(|| self.buffer_text());
+
+                let json = match message {
+                    Message::FormatJson => transform::format_json(&text, self.config.json_indent),
+                    _ => transform::minify_json(&text),
+                };
+
+                match (json, selection) {
+                    (Ok(json), Some(_)) => self.replace_selection(json),
+                    (Ok(json), None) => self.replace_text(&json),
+                    (Err(err), _) => self.error = Some(EditorError::TransformError(err)),
+                }
+            }
+            Message::ComputeChecksum(algorithm) => {
+                // A saved file is hashed as it is on disk, which is what a
+                // published checksum is compared against
+                let path = self
+                    .path
+                    .clone()
+                    .filter(|_| !self.is_dirty && self.tail.is_none());
+                let contents = self.line_ending.apply(&self.contents_to_save());
+
+                return Command::perform(
+                    compute_checksum(algorithm, path, contents),
+                    Message::ChecksumComputed,
+                );
+            }
+            Message::ChecksumComputed(Ok(checksum)) => {
+                self.checksum = Some(checksum);
+            }
+            Message::ChecksumComputed(Err(err)) => {
+                self.error = Some(err);
+            }
+            Message::CopyChecksum => {
+                if let Some(checksum) = &self.checksum {
+                    return clipboard::write(checksum.hex.clone());
+                }
+            }
+            Message::EvaluateExpression(_) if self.is_read_only => {}
+            Message::EvaluateExpression(mode) => {
+                let Some(selection) = self.content.selection() else {
+                    return Command::none();
+                };
+
+                match expression::apply(&selection, mode) {
+                    Ok(text) => self.replace_selection(text),
+                    Err(err) => self.error = Some(EditorError::TransformError(err)),
+                }
+            }
+            Message::IncrementNumber | Message::DecrementNumber if self.is_read_only => {}
+            Message::IncrementNumber | Message::DecrementNumber => {
+                let delta = match message {
+                    Message::IncrementNumber => 1,
+                    _ => -1,
+                };
+
+                if let Some(selection) = self.content.selection() {
+                    self.replace_selection(transform::step_numbers(&selection, delta));
+
+                    return Command::none();
+                }
+
+                let (line, column) = self.content.cursor_position();
+                let Some(text) = self.content.line(line).map(|text| text.to_string()) else {
+                    return Command::none();
+                };
+
+                let adjusted = transform::find_number(&text, column).and_then(|range| {
+                    transform::add_to_number(&text[range.clone()], delta)
+                        .map(|number| (range, number))
+                });
+
+                if let Some((range, number)) = adjusted {
+                    cursor::select(&mut self.content, line, range);
+                    self.replace_selection(number);
+                }
+            }
+            Message::AlignColumns(_) if self.is_read_only => {}
+            Message::AlignColumns(separator) => {
+                if let Some(selection) = self.content.selection() {
+                    self.replace_selection(transform::align_columns(&selection, separator));
+                }
+            }
+            Message::OpenTerminal => {
+                let folder = self.path.as_deref().map(absolute_path);
+                let folder = folder.as_deref().and_then(Path::parent);
+
+                if let (Some(folder), Some((program, args))) =
+                    (folder, self.config.terminal.split_first())
+                {
+                    let spawned = std::process::Command::new(program)
+                        .args(args)
+                        .current_dir(folder)
+                        .spawn();
+
+                    if let Err(err) = spawned {
+                        self.error = Some(err.into());
+                    }
+                }
+            }
+            Message::CloseChecksum => {
+                self.checksum = None;
+            }
+            Message::Indent
+                if self.is_read_only
+                    || self.palette.is_some()
+                    || self.find.is_some()
+                    || self.symbol_query.is_some()
+                    || self.note_draft.is_some()
+                    || self.tag_draft.is_some() => {}
+            Message::Indent => {
+                let (_, column) = self.content.cursor_position();
+                let indent =
+                    format::indent_unit(self.config.indent_style, self.config.indent_width, column);
+
+                self.is_dirty = true;
+                self.content
+                    .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(indent),
+                    )));
+                self.check_indentation();
+            }
+        }
+
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        // Keep a copy of unsaved work around for the panic hook
+        let snapshots = if self.is_dirty {
+            time::every(Duration::from_secs(2)).map(|_| Message::SnapshotBuffer)
+        } else {
+            Subscription::none()
+        };
+
+        // Waiting for the cursor to rest keeps navigation from re-highlighting
+        // the buffer on every step
+        let occurrences = if self.cursor_moved_at.is_some() {
+            time::every(Duration::from_millis(
+                self.config.occurrence_delay_ms.max(1),
+            ))
+            .map(|_| Message::HighlightOccurrences)
+        } else {
+            Subscription::none()
+        };
+
+        let follow = if self.following.is_some() {
+            time::every(Duration::from_secs(1)).map(|_| Message::CheckFollowed)
+        } else {
+            Subscription::none()
+        };
+
+        // Only listened to during a drag, since every pointer move would
+        // otherwise go through `update`
+        let dragging = if self.drag.is_some() {
+            Subscription::batch([
+                event::listen_with(|event, _status| match event {
+                    event::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                        Some(Message::PointerMoved(position))
+                    }
+                    event::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                        Some(Message::PointerReleased)
+                    }
+                    _ => None,
+                }),
+                time::every(Duration::from_millis(50)).map(|_| Message::DragScroll),
+            ])
+        } else {
+            Subscription::none()
+        };
+
+        let launches = if self.is_primary {
+            ipc::launches().map(Message::Launched)
+        } else {
+            Subscription::none()
+        };
+
+        let automation = match &self.automation {
+            Some(path) => ipc::automation(path.clone()).map(|request| match request {
+                Ok((request, reply)) => Message::Automation(request, reply),
+                Err(err) => Message::AutomationFailed(err),
+            }),
+            None => Subscription::none(),
+        };
+
+        let idle_save = if self.is_dirty
+            && self
+                .config
+                .auto_save
+                .contains(&config::AutoSaveTrigger::Idle)
+        {
+            time::every(Duration::from_millis(500))
+                .map(|_| Message::AutoSave(config::AutoSaveTrigger::Idle))
+        } else {
+            Subscription::none()
+        };
+
+        // Redrawn as it goes so the toast can fade
+        let toast = if matches!(self.toast, Some(Toast::Done(..))) {
+            time::every(Duration::from_millis(50)).map(|_| Message::FadeToast)
+        } else {
+            Subscription::none()
+        };
+
+        let find_notice = if self
+            .find
+            .as_ref()
+            .is_some_and(|find| find.wrapped.is_some())
+        {
+            time::every(Duration::from_millis(250)).map(|_| Message::ExpireFindNotice)
+        } else {
+            Subscription::none()
+        };
+
+        let smooth_scroll = if self.pending_scroll != 0 {
+            time::every(Duration::from_millis(16)).map(|_| Message::SmoothScroll)
+        } else {
+            Subscription::none()
+        };
+
+        // Rebuilt from the buffer at most this often, rather than on every edit
+        let outline = if self.outline.is_some() {
+            time::every(Duration::from_millis(500)).map(|_| Message::RefreshOutline)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([
+            dragging,
+            find_notice,
+            outline,
+            smooth_scroll,
+            toast,
+            idle_save,
+            launches,
+            automation,
+            appearance::changes().map(Message::AppearanceChanged),
+            snapshots,
+            occurrences,
+            follow,
+            keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+                keyboard::Key::Character("s") if modifiers.command() => Some(Message::Save),
+                keyboard::Key::Character("f") if modifiers.command() => Some(Message::OpenFind),
+                keyboard::Key::Named(keyboard::key::Named::F3) if modifiers.shift() => {
+                    Some(Message::FindPrevious)
+                }
+                keyboard::Key::Named(keyboard::key::Named::F3) => Some(Message::FindNext),
+                keyboard::Key::Named(keyboard::key::Named::F12) => Some(Message::PeekDefinition),
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                    Some(Message::RecallSearch { older: true })
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                    Some(Message::RecallSearch { older: false })
+                }
+                keyboard::Key::Character("p" | "P") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::OpenPalette)
+                }
+                keyboard::Key::Character("i" | "I") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::FindInvisibles)
+                }
+                keyboard::Key::Character("d" | "D") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::CompareFiles)
+                }
+                keyboard::Key::Character("o" | "O") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::OpenSymbols)
+                }
+                keyboard::Key::Character("t" | "T") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::TransposeWords)
+                }
+                keyboard::Key::Character("t") if modifiers.command() => {
+                    Some(Message::TransposeChars)
+                }
+                keyboard::Key::Character("y" | "Y") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::OpenPasteHistory)
+                }
+                keyboard::Key::Character("b" | "B") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::ToggleAllPanels)
+                }
+                keyboard::Key::Character("m" | "M") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::JumpToMatchingBracket)
+                }
+                keyboard::Key::Character("q" | "Q") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::ReflowParagraph)
+                }
+                keyboard::Key::Character("r" | "R") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::RepeatLastCommand)
+                }
+                // The text editor cuts on Ctrl+X whatever else is held, so
+                // Vim's Ctrl+X gives way to the Z beside it, and both take Alt
+                keyboard::Key::Character("a" | "A") if modifiers.command() && modifiers.alt() => {
+                    Some(Message::IncrementNumber)
+                }
+                keyboard::Key::Character("z" | "Z") if modifiers.command() && modifiers.alt() => {
+                    Some(Message::DecrementNumber)
+                }
+                keyboard::Key::Named(keyboard::key::Named::Tab) if modifiers.is_empty() => {
+                    Some(Message::Indent)
+                }
+                _ => None,
+            }),
+            event::listen_with(|event, _status| match event {
+                event::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
+                event::Event::Window(_, window::Event::CloseRequested) => {
+                    Some(Message::CloseRequested)
+                }
+                event::Event::Window(_, window::Event::Unfocused) => {
+                    Some(Message::AutoSave(config::AutoSaveTrigger::OnFocusLost))
+                }
+                // Copying and cutting, which the text editor does on its own
+                event::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Character(c),
+                    modifiers,
+                    ..
+                }) if modifiers.command() && (c == "c" || c == "x") => {
+                    Some(Message::ClipboardChanged)
+                }
+                // Listened to even when captured, since text inputs swallow it
+                event::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(Message::Escape),
+                _ => None,
+            }),
+        ])
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let controls = row![
+            action(new_icon(), "New file", (!self.wait).then_some(Message::New)),
+            action(
+                open_icon(),
+                "Open file",
+                (!self.wait).then_some(Message::Open)
+            ),
+            action(
+                save_icon(),
+                "Save file",
+                self.is_dirty.then_some(Message::Save)
+            ),
+            horizontal_space(),
+            pick_list(
+                fonts::families(&self.config.fonts),
+                Some(self.font_family()),
+                Message::FontSelected
+            ),
+            pick_list(
+                highlighter::Theme::ALL,
+                Some(self.buffer_theme()),
+                Message::ThemeSelected
+            )
+        ]
+        .spacing(10);
+
+        // Programming ligatures can't be toggled here: the text editor always
+        // shapes its buffer with `Shaping::Advanced` and exposes no font features.
+        // Its line height is likewise fixed, with no setter to adjust spacing, and
+        // it scrolls internally without drawing a scrollbar that could be styled.
+        // It always wraps, too, so very long lines only get their highlighting
+        // paused.
+        let input = container(
+            text_editor(&self.content)
+                .on_action(Message::Edit)
+                .style(theme::TextEditor::Custom(Box::new(Translucent(
+                    self.config.opacity(),
+                ))))
+                .font(self.font)
+                .highlight::<highlight::Highlighter>(
+                    highlight::Settings {
+                        syntax: highlighter::Settings {
+                            theme: self.buffer_theme(),
+                            extension: if self.is_highlighting_paused {
+                                String::from(language::PLAIN_TEXT)
+                            } else {
+                                self.language()
+                            },
+                        },
+                        word: self
+                            .occurrence
+                            .clone()
+                            .filter(|_| !self.is_highlighting_paused),
+                        search: self.find.as_ref().map(|find| highlight::Search {
+                            query: find.query.clone(),
+                            current: find.current,
+                            color: self
+                                .config
+                                .search_match_color
+                                .as_deref()
+                                .and_then(config::parse_color),
+                            current_color: self
+                                .config
+                                .current_match_color
+                                .as_deref()
+                                .and_then(config::parse_color),
+                        }),
+                    },
+                    highlight::Highlight::to_format,
+                )
+                .height(iced::Length::Fill),
+        )
+        .padding(10);
+
+        let input: Element<'_, Message> = match &self.outline {
+            Some(outline) => row![outline_view(outline), input].into(),
+            None => input.into(),
+        };
+
+        let input = match &self.comparison {
+            Some(comparison) => comparison_view(comparison),
+            None => input,
+        };
+
+        let status_bar = {
+            let status = if let Some(error) = &self.error {
+                text(error.to_string())
+            } else {
+                match self.path.as_deref().and_then(Path::to_str) {
+                    Some(path) => text(path).size(14),
+                    None => text("New file"),
+                }
+            };
+
+            let status = mouse_area(status).on_right_press(Message::TogglePathMenu);
+
+            let path_menu = self.is_path_menu_open.then(|| {
+                row![
+                    status_action(
+                        String::from("Copy path"),
+                        Message::RunCommand(Box::new(Message::CopyPath))
+                    ),
+                    status_action(
+                        String::from("Copy name"),
+                        Message::RunCommand(Box::new(Message::CopyFileName))
+                    ),
+                ]
+            });
+
+            let position = {
+                let (row, col) = self.content.cursor_position();
+
+                format!("{}:{}", row + 1, self.display_column(row, col) + 1)
+            };
+
+            let lines = text(format!(
+                "Ln {} of {}",
+                self.content.cursor_position().0 + 1,
+                self.content.line_count()
+            ))
+            .size(14);
+
+            // While highlighting is paused the buffer is drawn as plain text,
+            // so that's what the picker says
+            let shown = if self.is_highlighting_paused {
+                String::from(language::PLAIN_TEXT)
+            } else {
+                self.language()
+            };
+
+            let language = pick_list(
+                language::names(),
+                Some(language::name(&shown).to_string()),
+                Message::LanguageSelected,
+            )
+            .text_size(14)
+            .padding([2, 8]);
+
+            let highlighting = self
+                .unsupported_extension()
+                .map(|extension| text(format!("No highlighting for .{extension}")).size(14));
+
+            let indentation = status_action(
+                format::indentation_label(self.config.indent_style, self.config.indent_width),
+                Message::CycleIndentation,
+            );
+
+            let misindented = (!self.misindented.is_empty()).then(|| {
+                let count = self.misindented.len();
+                let label = match (self.config.indent_style, count) {
+                    (format::IndentStyle::Spaces, 1) => String::from("1 line indented with tabs"),
+                    (format::IndentStyle::Spaces, _) => format!("{count} lines indented with tabs"),
+                    (format::IndentStyle::Tabs, 1) => String::from("1 line indented with spaces"),
+                    (format::IndentStyle::Tabs, _) => format!("{count} lines indented with spaces"),
+                };
+
+                tooltip(
+                    status_action(label, Message::NormalizeIndentation),
+                    text("Normalize indentation").size(14),
+                    tooltip::Position::Top,
+                )
+                .style(theme::Container::Box)
+            });
+
+            let spared = self.spared_line();
+            let trailing = self
+                .trailing_whitespace
+                .iter()
+                .filter(|&&line| Some(line) != spared)
+                .count();
+            let trailing = (trailing > 0).then(|| {
+                tooltip(
+                    status_action(
+                        match trailing {
+                            1 => String::from("1 line with trailing whitespace"),
+                            n => format!("{n} lines with trailing whitespace"),
+                        },
+                        Message::TrimTrailingWhitespace,
+                    ),
+                    text("Trim trailing whitespace").size(14),
+                    tooltip::Position::Top,
+                )
+                .style(theme::Container::Box)
+            });
+
+            let line_ending =
+                status_action(self.line_ending.to_string(), Message::ToggleLineEnding);
+
+            let toast = self.toast.as_ref().map(|toast| -> Element<'_, Message> {
+                match toast {
+                    Toast::Saving => text("Saving…").size(14).into(),
+                    Toast::Done(message, at) => {
+                        let left = TOAST_DURATION.saturating_sub(at.elapsed());
+                        let alpha = (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+                        let color = self.theme().palette().text;
+
+                        text(message)
+                            .size(14)
+                            .style(Color { a: alpha, ..color })
+                            .into()
+                    }
+                    Toast::Failed(err) => container(
+                        row![
+                            text(format!("Not saved: {err}")).size(14),
+                            button(text("Dismiss").size(14))
+                                .on_press(Message::DismissToast)
+                                .style(theme::Button::Destructive)
+                                .padding([0, 6]),
+                        ]
+                        .spacing(10)
+                        .align_items(iced::Alignment::Center),
+                    )
+                    .padding([2, 6])
+                    .style(toast_error_appearance)
+                    .into(),
+                }
+            });
+
+            let project = self.project_root().map(|root| {
+                tooltip(
+                    status_action(
+                        format!("Project: {}", project::name(root)),
+                        Message::ChooseProjectRoot,
+                    ),
+                    text(root.display().to_string()).size(14),
+                    tooltip::Position::Top,
+                )
+                .style(theme::Container::Box)
+            });
+
+            // The text editor has no gutter to mark lines in, so the note on
+            // the cursor line shows here instead
+            let cursor_line = self.content.cursor_position().0;
+            let note = self.notes.get(&cursor_line).map(|note| {
+                tooltip(
+                    status_action(format!("Note: {note}"), Message::EditNote),
+                    text("Editor-only: notes are never saved into the file").size(14),
+                    tooltip::Position::Top,
+                )
+                .style(theme::Container::Box)
+            });
+            let notes = (!self.notes.is_empty()).then(|| {
+                let count = self.notes.len();
+
+                status_action(
+                    match count {
+                        1 => String::from("1 note"),
+                        _ => format!("{count} notes"),
+                    },
+                    Message::NextNote,
+                )
+            });
+
+            row![status]
+                .push_maybe(path_menu)
+                .push_maybe(project)
+                .push_maybe(note)
+                .push_maybe(notes)
+                .push(horizontal_space())
+                .push_maybe(toast)
+                .push_maybe(self.is_showing_opacity.then(|| {
+                    tooltip(
+                        slider(
+                            config::MIN_OPACITY..=1.0,
+                            self.config.opacity(),
+                            Message::OpacityChanged,
+                        )
+                        .on_release(Message::SaveOpacity)
+                        .step(0.05)
+                        .width(80),
+                        text(format!("Opacity {:.0}%", self.config.opacity() * 100.0)).size(14),
+                        tooltip::Position::Top,
+                    )
+                    .style(theme::Container::Box)
+                }))
+                .push_maybe(
+                    self.config
+                        .always_on_top
+                        .then(|| status_action(String::from("On top"), Message::ToggleAlwaysOnTop)),
+                )
+                .push_maybe(self.hidden_panels.is_some().then(|| {
+                    status_action(String::from("Panels hidden"), Message::ToggleAllPanels)
+                }))
+                .push_maybe(match self.following {
+                    Some(_) => Some(status_action(
+                        String::from("Following"),
+                        Message::ToggleFollow,
+                    )),
+                    None => self.is_read_only.then(|| {
+                        status_action(
+                            String::from(if self.is_write_protected {
+                                "Read-only: no write permission"
+                            } else {
+                                "Read-only"
+                            }),
+                            Message::ToggleReadOnly,
+                        )
+                    }),
+                })
+                .push_maybe(self.is_highlighting_paused.then(|| {
+                    tooltip(
+                        status_action(
+                            String::from("Highlighting off: long lines"),
+                            Message::ResumeHighlighting,
+                        ),
+                        text("Highlight anyway").size(14),
+                        tooltip::Position::Top,
+                    )
+                    .style(theme::Container::Box)
+                }))
+                .push_maybe(highlighting)
+                .push_maybe(misindented)
+                .push_maybe(trailing)
+                .push(indentation)
+                .push(text("UTF-8").size(14))
+                .push(line_ending)
+                .push(language)
+                .push(lines)
+                .push(Text::new(position))
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+        };
+
+        let invisibles = self.invisibles.as_ref().map(|invisibles| {
+            let summary = match invisibles.len() {
+                0 => String::from("No non-printable characters"),
+                1 => String::from("1 non-printable character"),
+                n => format!("{n} non-printable characters"),
+            };
+
+            let header = row![
+                text(summary).size(14),
+                horizontal_space(),
+                button(text("Strip all").size(14))
+                    .on_press_maybe((!invisibles.is_empty()).then_some(Message::StripInvisibles))
+                    .padding([2, 8]),
+                button(text("Close").size(14))
+                    .on_press(Message::CloseInvisibles)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+
+            let entries = column(invisibles.iter().map(|invisible| {
+                button(
+                    text(format!(
+                        "Ln {}, Col {}: U+{:04X}",
+                        invisible.line + 1,
+                        self.display_column(invisible.line, invisible.column) + 1,
+                        invisible.character as u32
+                    ))
+                    .size(14),
+                )
+                .on_press(Message::GoTo(invisible.line, invisible.column))
+                .style(theme::Button::Text)
+                .padding([2, 8])
+                .into()
+            }));
+
+            container(column![header, scrollable(entries)].spacing(5)).max_height(150)
+        });
+
+        let checksum = self.checksum.as_ref().map(|checksum| {
+            let source = if checksum.of_file { "file" } else { "buffer" };
+
+            row![
+                text(format!("{} of the {source}:", checksum.algorithm)).size(14),
+                text(&checksum.hex).size(14),
+                horizontal_space(),
+                button(text("Copy").size(14))
+                    .on_press(Message::CopyChecksum)
+                    .padding([2, 8]),
+                button(text("Close").size(14))
+                    .on_press(Message::CloseChecksum)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let empty_save_prompt = self.is_confirming_empty_save.then(|| {
+            row![
+                text("Save an empty file?").size(14),
+                horizontal_space(),
+                button(text("Save").size(14))
+                    .on_press(Message::ConfirmEmptySave)
+                    .padding([2, 8]),
+                button(text("Cancel").size(14))
+                    .on_press(Message::CancelEmptySave)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let close_prompt = self.is_confirming_close.then(|| {
+            row![
+                text("Save changes before closing?").size(14),
+                horizontal_space(),
+                button(text("Save").size(14))
+                    .on_press(Message::SaveAndClose)
+                    .padding([2, 8]),
+                button(text("Don't save").size(14))
+                    .on_press(Message::DiscardAndClose)
+                    .style(theme::Button::Destructive)
+                    .padding([2, 8]),
+                button(text("Cancel").size(14))
+                    .on_press(Message::CancelClose)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let recovered = self.recovered.as_ref().map(|snapshot| {
+            let source = match snapshot.path.as_deref().and_then(Path::to_str) {
+                Some(path) => format!("Unsaved changes to {path} were recovered from a crash"),
+                None => String::from("An unsaved buffer was recovered from a crash"),
+            };
+
+            row![
+                text(source).size(14),
+                horizontal_space(),
+                button(text("Restore").size(14))
+                    .on_press(Message::RestoreRecovered)
+                    .padding([2, 8]),
+                button(text("Discard").size(14))
+                    .on_press(Message::DiscardRecovered)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let permission = match &self.error {
+            Some(EditorError::PermissionDenied(path)) => Some(
+                row![
+                    text(if self.is_dirty {
+                        format!(
+                            "You don't have permission to write {}; your changes are still here",
+                            path.display()
+                        )
+                    } else {
+                        format!(
+                            "You don't have permission to write {}, so it's open read-only",
+                            path.display()
+                        )
+                    })
+                    .size(14),
+                    horizontal_space(),
+                ]
+                .push_maybe(cfg!(target_os = "linux").then(|| {
+                    button(text("Save as administrator").size(14))
+                        .on_press(Message::SaveElevated)
+                        .padding([2, 8])
+                }))
+                .push(
+                    button(text("Save as...").size(14))
+                        .on_press_maybe((!self.wait).then_some(Message::SaveAs))
+                        .style(theme::Button::Secondary)
+                        .padding([2, 8]),
+                )
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            ),
+            _ => None,
+        };
+
+        let crashed = self.crashed_session.as_ref().map(|session| {
+            row![
+                text(format!(
+                    "The editor didn't exit cleanly while editing {}",
+                    session.path.display()
+                ))
+                .size(14),
+                horizontal_space(),
+                button(text("Reopen").size(14))
+                    .on_press_maybe((!self.wait).then_some(Message::ReopenCrashed))
+                    .padding([2, 8]),
+                button(text("Dismiss").size(14))
+                    .on_press(Message::DismissCrashed)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let templates = self.is_offering_templates.then(|| {
+            row![text("Start from a template:").size(14)]
+                .extend(self.offered_templates().into_iter().map(|path| {
+                    button(text(template_name(path)).size(14))
+                        .on_press(Message::NewFromTemplate(path.clone()))
+                        .style(theme::Button::Secondary)
+                        .padding([2, 8])
+                        .into()
+                }))
+                .push(horizontal_space())
+                .push(
+                    button(text("Dismiss").size(14))
+                        .on_press(Message::DismissTemplates)
+                        .style(theme::Button::Secondary)
+                        .padding([2, 8]),
+                )
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+        });
+
+        let queued = self.queued_files.first().map(|(path, _)| {
+            let count = self.queued_files.len();
+            let label = match count {
+                1 => format!("1 more file picked: {}", path.display()),
+                _ => format!("{count} more files picked, next: {}", path.display()),
+            };
+
+            row![
+                text(label).size(14),
+                horizontal_space(),
+                button(text("Open next").size(14))
+                    .on_press_maybe((!self.wait).then_some(Message::OpenNext))
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let tail = self.tail.filter(|&start| start > 0).map(|start| {
+            row![
+                text(format!("Showing the file from byte {start} on")).size(14),
+                horizontal_space(),
+                button(text("Load more").size(14))
+                    .on_press(Message::LoadMore)
+                    .padding([2, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let palette = self.palette.as_deref().map(|query| {
+            let input = text_input("Type a command...", query)
+                .id(palette_input())
+                .on_input(Message::PaletteChanged)
+                .on_submit(Message::SubmitPalette)
+                .size(14)
+                .padding([4, 8]);
+
+            let commands = column(
+                self.commands()
+                    .into_iter()
+                    .filter(|(label, _)| palette::matches(query, label))
+                    .map(|(label, command)| {
+                        button(text(label).size(14))
+                            .on_press_maybe(
+                                command.map(|command| Message::RunCommand(Box::new(command))),
+                            )
+                            .width(iced::Length::Fill)
+                            .style(theme::Button::Text)
+                            .padding([2, 8])
+                            .into()
+                    }),
+            );
+
+            container(column![input, scrollable(commands)].spacing(5))
+                .max_height(250)
+                .padding(5)
+                .style(theme::Container::Box)
+        });
+
+        let find = self.find.as_ref().map(|find| {
+            let notice = match find.wrapped {
+                _ if find.has_no_matches => Some("No matches"),
+                Some((search::Direction::Forward, _)) => Some("Search wrapped to top"),
+                Some((search::Direction::Backward, _)) => Some("Search wrapped to bottom"),
+                None => None,
+            };
+
+            row![
+                text_input("Find... (Up and Down for recent searches)", &find.query)
+                    .id(find_input())
+                    .on_input(Message::FindChanged)
+                    .on_submit(Message::SubmitFind)
+                    .size(14)
+                    .padding([4, 8])
+            ]
+            .push_maybe(notice.map(|notice| text(notice).size(14)))
+            .push(
+                button(text("Previous").size(14))
+                    .on_press(Message::FindPrevious)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            )
+            .push(
+                button(text("Next").size(14))
+                    .on_press(Message::FindNext)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            )
+            .push(
+                button(text("Close").size(14))
+                    .on_press(Message::CloseFind)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            )
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let paste_history = self.is_picking_paste.then(|| {
+            let entries = column(self.clipboard_ring.iter().enumerate().map(
+                |(index, contents)| {
+                    let first_line = contents.lines().next().unwrap_or_default();
+                    let preview: String = first_line.chars().take(80).collect();
+                    let lines = contents.lines().count();
+
+                    button(
+                        row![text(preview).font(self.font).size(14), horizontal_space(),]
+                            .push_maybe(
+                                (lines > 1).then(|| text(format!("{lines} lines")).size(14)),
+                            ),
+                    )
+                    .on_press(Message::PasteFromHistory(index))
+                    .width(iced::Length::Fill)
+                    .style(theme::Button::Text)
+                    .padding([2, 8])
+                    .into()
+                },
+            ));
+
+            container(column![text("Paste from history").size(14), scrollable(entries)].spacing(5))
+                .max_height(250)
+                .padding(5)
+                .style(theme::Container::Box)
+        });
+
+        let peek = self.peek.as_ref().map(|(name, lines)| {
+            let header = row![
+                text(match lines.len() {
+                    0 => format!("No definition of {name} found"),
+                    1 => format!("Definition of {name}"),
+                    n => format!("{n} definitions of {name}"),
+                })
+                .size(14),
+                horizontal_space(),
+                button(text("Close").size(14))
+                    .on_press(Message::ClosePeek)
+                    .style(theme::Button::Secondary)
+                    .padding([2, 8]),
+            ]
+            .align_items(iced::Alignment::Center);
+
+            let definitions = column(lines.iter().map(|&line| {
+                let context = (line..line + self.config.peek_lines.max(1))
+                    .map_while(|line| self.content.line(line).map(|text| text.to_string()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                button(
+                    row![
+                        text(context).font(self.font).size(14),
+                        horizontal_space(),
+                        text(format!("Ln {}", line + 1)).size(14),
+                    ]
+                    .spacing(10),
+                )
+                .on_press(Message::JumpToSymbol(line))
+                .width(iced::Length::Fill)
+                .style(theme::Button::Text)
+                .padding([2, 8])
+                .into()
+            }))
+            .spacing(5);
+
+            container(column![header, scrollable(definitions)].spacing(5))
+                .max_height(300)
+                .padding(5)
+                .style(theme::Container::Box)
+        });
+
+        let symbols = self.symbol_query.as_deref().map(|query| {
+            let input = text_input("Go to symbol...", query)
+                .id(symbol_input())
+                .on_input(Message::SymbolQueryChanged)
+                .on_submit(Message::SubmitSymbols)
+                .size(14)
+                .padding([4, 8]);
+
+            let symbols = column(self.matching_symbols().into_iter().map(|symbol| {
+                button(
+                    row![
+                        text(symbol.label).size(14),
+                        horizontal_space(),
+                        text(format!("Ln {}", symbol.line + 1)).size(14),
+                    ]
+                    .padding([0, 0, 0, symbol.depth as u16 * 16]),
+                )
+                .on_press(Message::JumpToSymbol(symbol.line))
+                .width(iced::Length::Fill)
+                .style(theme::Button::Text)
+                .padding([2, 8])
+                .into()
+            }));
+
+            container(column![input, scrollable(symbols)].spacing(5))
+                .max_height(250)
+                .padding(5)
+                .style(theme::Container::Box)
+        });
+
+        let tag = self.tag_draft.as_deref().map(|draft| {
+            row![
+                text("Wrap selection with tag:").size(14),
+                text_input("div class=\"note\"", draft)
+                    .id(tag_input())
+                    .on_input(Message::TagChanged)
+                    .on_submit(Message::SubmitTag)
+                    .size(14)
+                    .padding([4, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        let note = self.note_draft.as_deref().map(|draft| {
+            let line = self.content.cursor_position().0;
+
+            row![
+                text(format!("Note on line {}:", line + 1)).size(14),
+                text_input("Only kept by the editor, never saved into the file", draft)
+                    .id(note_input())
+                    .on_input(Message::NoteChanged)
+                    .on_submit(Message::SubmitNote)
+                    .size(14)
+                    .padding([4, 8]),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        });
+
+        container(
+            column![controls]
+                .push_maybe(palette)
+                .push_maybe(find)
+                .push_maybe(paste_history)
+                .push_maybe(peek)
+                .push_maybe(symbols)
+                .push_maybe(note)
+                .push_maybe(tag)
+                .push_maybe(recovered)
+                .push_maybe(crashed)
+                .push_maybe(templates)
+                .push_maybe(queued)
+                .push_maybe(tail)
+                .push(input)
+                .push_maybe(invisibles)
+                .push_maybe(checksum)
+                .push_maybe(permission)
+                .push_maybe(empty_save_prompt)
+                .push_maybe(close_prompt)
+                .push(status_bar),
+        )
+        .padding(10)
+        .into()
+    }
+
+    fn style(&self) -> theme::Application {
+        let opacity = self.config.opacity();
+
+        theme::Application::custom(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+
+            iced::application::Appearance {
+                background_color: Color {
+                    a: opacity,
+                    ..palette.background.base.color
+                },
+                text_color: palette.background.base.text,
+            }
+        })
+    }
+
+    fn theme(&self) -> iced::Theme {
+        if self.buffer_theme().is_dark() {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
+
+impl Editor {
+    /// Empties the line if it holds nothing but whitespace, keeping the cursor
+    /// where it is.
+    fn trim_line(&mut self, line: usize) {
+        let Some(whitespace) = self
+            .content
+            .line(line)
+            .filter(|text| !text.is_empty() && text.trim().is_empty())
+            .map(|text| text.len())
+        else {
+            return;
+        };
+
+        let (row, column) = self.content.cursor_position();
+
+        cursor::move_to(&mut self.content, line, whitespace);
+        self.content
+            .perform(text_editor::Action::Select(text_editor::Motion::Home));
+        self.content
+            .perform(text_editor::Action::Edit(text_editor::Edit::Delete));
+
+        let column = if row == line { 0 } else { column };
+        cursor::move_to(&mut self.content, row, column);
+    }
+
+    /// Finds the lines indented against the indentation setting.
+    fn check_indentation(&mut self) {
+        let text = self.content.text();
+
+        self.misindented =
+            format::misindented_lines(&text, self.config.indent_style, self.config.indent_width);
+        self.trailing_whitespace = format::trailing_whitespace_lines(&text);
+    }
+
+    /// Puts the text at the front of the clipboard history, dropping the
+    /// oldest one past the limit.
+    fn remember_clipboard(&mut self, contents: String) {
+        if contents.is_empty() {
+            return;
+        }
+
+        self.clipboard_ring.retain(|recent| *recent != contents);
+        self.clipboard_ring.insert(0, contents);
+        self.clipboard_ring.truncate(self.config.clipboard_history);
+    }
+
+    /// The (0-based) column to show for a byte column of a line, counted the
+    /// configured way.
+    fn display_column(&self, line: usize, column: usize) -> usize {
+        match self.config.column_display {
+            config::ColumnDisplay::Visual => {
+                cursor::visual_column(&self.content, line, column, self.config.indent_width)
+            }
+            config::ColumnDisplay::Characters => cursor::char_column(&self.content, line, column),
+        }
+    }
+
+    /// The line whitespace checks leave alone, if the cursor's is spared.
+    fn spared_line(&self) -> Option<usize> {
+        self.config
+            .spare_cursor_line
+            .then(|| self.content.cursor_position().0)
+    }
+
+    /// Removes the spaces and tabs ending a line that has more than that,
+    /// keeping the cursor where it is.
+    fn trim_trailing(&mut self, line: usize) {
+        let Some(range) = self
+            .content
+            .line(line)
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| text.trim_end_matches([' ', '\t']).len()..text.len())
+            .filter(|range| !range.is_empty())
+        else {
+            return;
+        };
+
+        let (row, column) = self.content.cursor_position();
+
+        cursor::select(&mut self.content, line, range.clone());
+        self.content
+            .perform(text_editor::Action::Edit(text_editor::Edit::Delete));
+
+        let column = if row == line {
+            column.min(range.start)
+        } else {
+            column
+        };
+        cursor::move_to(&mut self.content, row, column);
+    }
+
+    /// Highlights the occurrences of the selected text if it's on a single
+    /// line, or else of the word under the cursor.
+    fn highlight_occurrences(&mut self) {
+        let (line, column) = self.content.cursor_position();
+
+        self.cursor_moved_at = None;
+        self.occurrence = match self.selected_text() {
+            Some(selection) => Some(selection).filter(|text| !text.contains('\n')),
+            None if self.content.selection().is_some() => None,
+            None => self
+                .content
+                .line(line)
+                .and_then(|line| highlight::word_at(&line, column).map(String::from)),
+        };
+    }
+
+    /// The text selection-driven features work on. Surrounding whitespace is
+    /// dropped unless configured otherwise, while the selection itself stays.
+    fn selected_text(&self) -> Option<String> {
+        let selection = self.content.selection()?;
+
+        let text = if self.config.trim_selection {
+            selection.trim().to_string()
+        } else {
+            selection
+        };
+
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// The highlighter token for the current buffer: an explicit choice wins,
+    /// then detection, then whatever was picked for this file last time.
+    fn language(&self) -> String {
+        let first_line = self.content.line(0);
+
+        self.language
+            .clone()
+            .or_else(|| {
+                language::detect(
+                    self.path.as_deref(),
+                    first_line.as_deref().unwrap_or_default(),
+                )
+            })
+            .or_else(|| {
+                let path = absolute_path(self.path.as_deref()?);
+
+                self.config.language_overrides.get(&path).cloned()
+            })
+            .unwrap_or_else(|| match self.path {
+                Some(_) => String::from(language::PLAIN_TEXT),
+                None => String::from("rs"),
+            })
+    }
+
+    /// The bracket and quote pairs of the current language.
+    fn bracket_pairs(&self) -> Vec<(char, char)> {
+        brackets::pairs(&self.config.bracket_pairs, language::name(&self.language()))
+    }
+
+    /// Puts the text around the selection, or with nothing selected, inserts
+    /// both with the cursor between them.
+    fn wrap_selection(&mut self, open: &str, close: &str) {
+        match self.content.selection() {
+            Some(selection) => self.replace_selection(format!("{open}{selection}{close}")),
+            None => {
+                self.replace_selection(format!("{open}{close}"));
+
+                for _ in close.chars() {
+                    self.content
+                        .perform(text_editor::Action::Move(text_editor::Motion::Left));
+                }
+            }
+        }
+    }
+
+    /// The commands offered by the command palette, with `None` for those
+    /// that aren't available right now.
+    fn commands(&self) -> Vec<(String, Option<Message>)> {
+        let has_path = self.path.is_some();
+        let can_edit = !self.is_read_only;
+        let has_selection = can_edit && self.content.selection().is_some();
+
+        vec![
+            ("New file", (!self.wait).then_some(Message::New)),
+            ("Open file", (!self.wait).then_some(Message::Open)),
+            (
+                "Open tail of file...",
+                (!self.wait).then_some(Message::OpenTail),
+            ),
+            ("Save file", Some(Message::Save)),
+            (
+                "Save as... (switch to the new file)",
+                (!self.wait).then_some(Message::SaveAs),
+            ),
+            (
+                "Save a copy... (keep editing this file)",
+                Some(Message::SaveCopy),
+            ),
+            ("Compare files...", Some(Message::CompareFiles)),
+            (
+                "Find non-printable characters",
+                Some(Message::FindInvisibles),
+            ),
+            (
+                "Toggle read-only",
+                (self.tail.is_none() && self.following.is_none())
+                    .then_some(Message::ToggleReadOnly),
+            ),
+            (
+                "Toggle line ending (LF/CRLF)",
+                can_edit.then_some(Message::ToggleLineEnding),
+            ),
+            (
+                "Follow the system light/dark theme",
+                (self.config.theme_mode != config::ThemeMode::System || self.is_theme_pinned)
+                    .then_some(Message::UseSystemTheme),
+            ),
+            (
+                "Toggle theme for this buffer only",
+                Some(Message::ToggleThemeOverride),
+            ),
+            ("Change indentation", Some(Message::CycleIndentation)),
+            (
+                "Toggle block comment",
+                (can_edit && language::block_comment(&self.language()).is_some())
+                    .then_some(Message::ToggleBlockComment),
+            ),
+            (
+                "Sort lines by length, shortest first",
+                can_edit.then_some(Message::SortByLength {
+                    longest_first: false,
+                }),
+            ),
+            (
+                "Sort lines by length, longest first",
+                can_edit.then_some(Message::SortByLength {
+                    longest_first: true,
+                }),
+            ),
+            ("Shuffle lines", can_edit.then_some(Message::ShuffleLines)),
+            (
+                "Trim trailing whitespace",
+                can_edit.then_some(Message::TrimTrailingWhitespace),
+            ),
+            (
+                "Transpose characters",
+                can_edit.then_some(Message::TransposeChars),
+            ),
+            (
+                "Transpose words",
+                can_edit.then_some(Message::TransposeWords),
+            ),
+            (
+                "Reflow paragraph",
+                can_edit.then_some(Message::ReflowParagraph),
+            ),
+            (
+                "Collapse multiple blank lines",
+                can_edit.then_some(Message::SqueezeBlankLines),
+            ),
+            (
+                match self.config.punctuation {
+                    transform::Punctuation::Straight => "Normalize punctuation (straight quotes)",
+                    transform::Punctuation::Smart => "Normalize punctuation (smart quotes)",
+                },
+                can_edit.then_some(Message::NormalizePunctuation),
+            ),
+            (
+                "Base64 encode selection",
+                has_selection.then_some(Message::EncodeSelection(transform::EncodeKind::Base64)),
+            ),
+            (
+                "Base64 decode selection",
+                has_selection.then_some(Message::DecodeSelection(transform::EncodeKind::Base64)),
+            ),
+            (
+                "URL encode selection",
+                has_selection.then_some(Message::EncodeSelection(transform::EncodeKind::Url)),
+            ),
+            (
+                "URL decode selection",
+                has_selection.then_some(Message::DecodeSelection(transform::EncodeKind::Url)),
+            ),
+            (
+                "Evaluate selection",
+                has_selection.then_some(Message::EvaluateExpression(expression::Mode::Replace)),
+            ),
+            (
+                "Evaluate selection and append = result",
+                has_selection.then_some(Message::EvaluateExpression(expression::Mode::Append)),
+            ),
+            (
+                "Increment number",
+                can_edit.then_some(Message::IncrementNumber),
+            ),
+            (
+                "Decrement number",
+                can_edit.then_some(Message::DecrementNumber),
+            ),
+            ("Format JSON", can_edit.then_some(Message::FormatJson)),
+            ("Minify JSON", can_edit.then_some(Message::MinifyJson)),
+            (
+                "Normalize indentation",
+                can_edit.then_some(Message::NormalizeIndentation),
+            ),
+            (
+                "Convert indentation: tabs → spaces",
+                can_edit.then_some(Message::ConvertIndentation(format::IndentStyle::Spaces)),
+            ),
+            (
+                "Convert indentation: spaces → tabs",
+                can_edit.then_some(Message::ConvertIndentation(format::IndentStyle::Tabs)),
+            ),
+            (
+                "Toggle follow mode (tail -f)",
+                (self.following.is_some() || (self.file_len.is_some() && !self.is_dirty))
+                    .then_some(Message::ToggleFollow),
+            ),
+            (
+                "Repeat last command",
+                self.last_command
+                    .is_some()
+                    .then_some(Message::RepeatLastCommand),
+            ),
+            ("Find", Some(Message::OpenFind)),
+            ("Go to symbol", Some(Message::OpenSymbols)),
+            ("Peek definition", Some(Message::PeekDefinition)),
+            (
+                "Go to matching bracket",
+                Some(Message::JumpToMatchingBracket),
+            ),
+            (
+                "Paste from history",
+                (can_edit && !self.clipboard_ring.is_empty()).then_some(Message::OpenPasteHistory),
+            ),
+            (
+                if self.hidden_panels.is_some() {
+                    "Show hidden panels"
+                } else {
+                    "Hide all panels"
+                },
+                Some(Message::ToggleAllPanels),
+            ),
+            (
+                "Toggle outline",
+                (self.outline.is_some() || language::name(&self.language()) == "Markdown")
+                    .then_some(Message::ToggleOutline),
+            ),
+            (
+                "Open terminal here",
+                has_path.then_some(Message::OpenTerminal),
+            ),
+            ("Copy file path", has_path.then_some(Message::CopyPath)),
+            ("Copy file name", has_path.then_some(Message::CopyFileName)),
+            (
+                "Clear recent files",
+                (!self.config.recent_files.is_empty()).then_some(Message::ClearRecentFiles),
+            ),
+            ("Clear history", Some(Message::ClearHistory)),
+            (
+                if self.config.always_on_top {
+                    "Stop keeping the window on top"
+                } else {
+                    "Keep the window on top"
+                },
+                Some(Message::ToggleAlwaysOnTop),
+            ),
+            ("Add or edit note on this line", Some(Message::EditNote)),
+            (
+                "Remove note on this line",
+                self.notes
+                    .contains_key(&self.content.cursor_position().0)
+                    .then_some(Message::RemoveNote),
+            ),
+            (
+                "Next note",
+                (!self.notes.is_empty()).then_some(Message::NextNote),
+            ),
+            ("Refresh templates", Some(Message::RefreshTemplates)),
+            ("Choose project root...", Some(Message::ChooseProjectRoot)),
+            (
+                "Detect project root",
+                (has_path || self.root_override.is_some()).then_some(Message::DetectProjectRoot),
+            ),
+        ]
+        .into_iter()
+        .map(|(label, command)| (label.to_string(), command))
+        .chain(
+            [
+                (
+                    String::from("Align columns on spaces"),
+                    transform::Separator::Spaces,
+                ),
+                (
+                    String::from("Align columns on |"),
+                    transform::Separator::Every('|'),
+                ),
+                (
+                    String::from("Align columns on ,"),
+                    transform::Separator::Every(','),
+                ),
+                (
+                    String::from("Align on first ="),
+                    transform::Separator::First('='),
+                ),
+                (
+                    String::from("Align on first :"),
+                    transform::Separator::First(':'),
+                ),
+            ]
+            .into_iter()
+            .map(move |(label, separator)| {
+                (
+                    label.to_string(),
+                    has_selection.then_some(Message::AlignColumns(separator)),
+                )
+            }),
+        )
+        .chain(self.bracket_pairs().into_iter().map(|(open, close)| {
+            (
+                format!("Wrap selection with {open}{close}"),
+                can_edit.then_some(Message::WrapSelection(brackets::Pair::Chars(open, close))),
+            )
+        }))
+        .chain([(
+            String::from("Wrap selection with tag"),
+            can_edit.then_some(Message::WrapSelection(brackets::Pair::Tag)),
+        )])
+        .chain([100, 90, 80, 70, 50].map(|percent| {
+            (
+                format!("Window opacity: {percent}%"),
+                Some(Message::SetOpacity(percent as f32 / 100.0)),
+            )
+        }))
+        .chain(checksum::Algorithm::ALL.into_iter().map(|algorithm| {
+            (
+                format!("Checksum: {algorithm}"),
+                Some(Message::ComputeChecksum(algorithm)),
+            )
+        }))
+        .chain(self.templates.iter().map(|path| {
+            (
+                format!("New from template: {}", template_name(path)),
+                (!self.wait).then(|| Message::NewFromTemplate(path.clone())),
+            )
+        }))
+        .chain(self.config.recent_files.iter().map(|path| {
+            (
+                format!("Open recent: {}", path.display()),
+                (!self.wait).then(|| Message::OpenRecent(path.clone())),
+            )
+        }))
+        .collect()
+    }
+
+    /// Moves the notes below an edit that added or removed lines along with
+    /// their lines, dropping those of lines it removed.
+    fn shift_notes(&mut self, line: usize, line_count: usize) {
+        let delta = self.content.line_count() as isize - line_count as isize;
+
+        if delta == 0 || self.notes.is_empty() {
+            return;
+        }
+
+        let start = line.min(self.content.cursor_position().0);
+
+        self.notes = std::mem::take(&mut self.notes)
+            .into_iter()
+            .filter_map(|(line, note)| {
+                if line <= start {
+                    return Some((line, note));
+                }
+
+                let shifted = line as isize + delta;
+
+                (shifted > start as isize).then_some((shifted as usize, note))
+            })
+            .collect();
+
+        // Written out with the rest of the config on exit, rather than on
+        // every line typed
+        self.remember_notes();
+    }
+
+    /// Keeps the notes of the file in the config.
+    fn remember_notes(&mut self) {
+        let Some(path) = self.path.as_deref().map(absolute_path) else {
+            return;
+        };
+
+        if self.notes.is_empty() {
+            self.config.notes.remove(&path);
+        } else {
+            self.config.notes.insert(path, self.notes.clone());
+        }
+    }
+
+    /// Keeps the notes of the file in the config, saving it.
+    fn store_notes(&mut self) -> Command<Message> {
+        self.remember_notes();
+
+        Command::perform(
+            config::save_config(self.config.clone()),
+            Message::ConfigSaved,
+        )
+    }
+
+    /// The root project features work from: the one picked by hand, or else
+    /// the one found above the file.
+    fn project_root(&self) -> Option<&Path> {
+        self.root_override
+            .as_deref()
+            .or(self.project_root.as_deref())
+    }
+
+    /// Looks for the project root above the file, in the background.
+    fn detect_root(&self) -> Command<Message> {
+        match &self.path {
+            Some(path) => Command::perform(
+                project::find_root(absolute_path(path), self.config.root_markers.clone()),
+                Message::RootDetected,
+            ),
+            None => Command::none(),
+        }
+    }
+
+    /// The templates offered for a new buffer: those for its language once one
+    /// is picked, or else all of them.
+    fn offered_templates(&self) -> Vec<&PathBuf> {
+        let name = language::name(&self.language());
+
+        let matching: Vec<_> = self
+            .templates
+            .iter()
+            .filter(|path| {
+                path.extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| language::name(extension) == name)
+            })
+            .collect();
+
+        if self.language.is_none() || matching.is_empty() {
+            self.templates.iter().collect()
+        } else {
+            matching
+        }
+    }
+
+    /// Closes or clears what Escape backs out of at the step, returning
+    /// whether there was anything.
+    fn back_out(&mut self, step: config::EscapeStep) -> bool {
+        match step {
+            config::EscapeStep::Overlay => {
+                let was_open = self.palette.is_some()
+                    || self.find.is_some()
+                    || self.is_picking_paste
+                    || self.peek.is_some()
+                    || self.symbol_query.is_some()
+                    || self.note_draft.is_some()
+                    || self.tag_draft.is_some()
+                    || self.is_path_menu_open
+                    || self.is_confirming_close
+                    || self.is_confirming_empty_save
+                    || self.invisibles.is_some()
+                    || self.checksum.is_some();
+
+                self.palette = None;
+                self.find = None;
+                self.is_picking_paste = false;
+                self.peek = None;
+                self.symbol_query = None;
+                self.note_draft = None;
+                self.tag_draft = None;
+                self.is_path_menu_open = false;
+                self.is_confirming_close = false;
+                self.close_after_save &= !self.is_confirming_empty_save;
+                self.is_confirming_empty_save = false;
+                self.invisibles = None;
+                self.checksum = None;
+
+                was_open
+            }
+            config::EscapeStep::Selection => {
+                let has_selection = self.content.selection().is_some();
+
+                if has_selection {
+                    self.content
+                        .perform(text_editor::Action::Move(text_editor::Motion::Right));
+                }
+
+                has_selection
+            }
+            config::EscapeStep::Notice => {
+                let had_notice = self.error.is_some() || self.toast.is_some();
+
+                self.error = None;
+                self.toast = None;
+
+                had_notice
+            }
+        }
+    }
+
+    /// The symbols of the buffer that match the symbol list's filter.
+    fn matching_symbols(&self) -> Vec<symbols::Symbol> {
+        let query = self.symbol_query.as_deref().unwrap_or_default();
+
+        symbols::find(&self.buffer_text(), &self.language())
+            .into_iter()
+            .filter(|symbol| palette::matches(query, &symbol.label))
+            .collect()
+    }
+
+    /// The buffer text with the final newline policy for its file type applied.
+    fn contents_to_save(&self) -> String {
+        let policy = self
+            .path
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.config.final_newline_overrides.get(extension))
+            .copied()
+            .unwrap_or(self.config.final_newline);
+
+        policy.apply(self.buffer_text(), self.had_final_newline)
+    }
+
+    /// The lines of the buffer joined by `\n`. Unlike `Content::text`, this
+    /// adds no trailing newline of its own.
+    fn buffer_text(&self) -> String {
+        self.content
+            .lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Whether these exact contents were saved moments ago, in which case
+    /// saving again (say, auto-save right after Ctrl+S) would only cause a
+    /// redundant write.
+    fn was_just_saved(&self, hash: u64) -> bool {
+        let debounce = Duration::from_millis(self.config.save_debounce_ms);
+
+        self.last_save
+            .is_some_and(|(at, saved)| saved == hash && at.elapsed() < debounce)
+    }
+
+    /// Carries out an automation request through the usual messages.
+    fn automate(&mut self, request: ipc::Request) -> (ipc::Response, Command<Message>) {
+        match request {
+            ipc::Request::GetContent => (
+                ipc::Response {
+                    content: Some(self.buffer_text()),
+                    ..ipc::Response::ok()
+                },
+                Command::none(),
+            ),
+            ipc::Request::GetCursor => {
+                let (line, column) = self.content.cursor_position();
+
+                (
+                    ipc::Response {
+                        line: Some(line + 1),
+                        column: Some(cursor::char_column(&self.content, line, column) + 1),
+                        ..ipc::Response::ok()
+                    },
+                    Command::none(),
+                )
+            }
+            ipc::Request::Insert { .. } if self.is_read_only => (
+                ipc::Response::error("the buffer is read-only"),
+                Command::none(),
+            ),
+            ipc::Request::Insert { text } => {
+                let command = self.update(Message::Edit(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(text)),
+                )));
+
+                (ipc::Response::ok(), command)
+            }
+            ipc::Request::Goto { line, column } => {
+                let line = line.saturating_sub(1);
+                let column =
+                    cursor::byte_column(&self.content, line, column.unwrap_or(1).saturating_sub(1));
+
+                (
+                    ipc::Response::ok(),
+                    self.update(Message::GoTo(line, column)),
+                )
+            }
+            ipc::Request::Save => (ipc::Response::ok(), self.update(Message::Save)),
+        }
+    }
+
+    /// The theme of the current buffer: its own override, or the global one.
+    fn buffer_theme(&self) -> highlighter::Theme {
+        self.theme_override.unwrap_or(self.theme)
+    }
+
+    /// The open file and the cursor position in it.
+    fn session(&self) -> Option<config::Session> {
+        self.path.as_deref().map(|path| {
+            let (line, column) = self.content.cursor_position();
+
+            config::Session {
+                path: absolute_path(path),
+                line,
+                column: cursor::char_column(&self.content, line, column),
+            }
+        })
+    }
+
+    /// Records where editing left off and closes the window once the config
+    /// is written.
+    fn exit(&mut self) -> Command<Message> {
+        self.config.session = self.session();
+
+        Command::perform(config::save_config(self.config.clone()), |_| Message::Exit)
+    }
+
+    /// Writes the buffer to its file, picking a name first for a new one.
+    fn save(&mut self) -> Command<Message> {
+        let contents = self.line_ending.apply(&self.contents_to_save());
+        let hash = content_hash(&contents);
+
+        if self.saving.is_some() || self.was_just_saved(hash) {
+            return Command::none();
+        }
+
+        self.saving = Some(hash);
+        self.toast = Some(Toast::Saving);
+
+        // A new file's line endings depend on the name it's given
+        if self.path.is_none() {
+            return Command::perform(pick_save_path(), Message::NewFileNamed);
+        }
+
+        Command::perform(save_file(self.path.clone(), contents), Message::FileSaved)
+    }
+
+    /// Replaces the selection (or inserts at the cursor) with the text.
+    fn replace_selection(&mut self, text: String) {
+        self.is_dirty = true;
+        self.content
+            .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                Arc::new(text),
+            )));
+        self.check_indentation();
+    }
+
+    /// Replaces the whole buffer, keeping the cursor where it was.
+    fn replace_text(&mut self, text: &str) {
+        let (line, column) = self.content.cursor_position();
+
+        self.content = text_editor::Content::with_text(text);
+        self.is_dirty = true;
+        self.check_indentation();
+
+        cursor::move_to(&mut self.content, line, column);
+    }
+
+    /// The name of the editor font family, as listed in the font picker.
+    fn font_family(&self) -> String {
+        self.config
+            .font
+            .clone()
+            .filter(|family| fonts::families(&self.config.fonts).contains(family))
+            .unwrap_or_else(|| String::from(fonts::DEFAULT))
+    }
+
+    /// The extension of the current file when it left the buffer unhighlighted.
+    fn unsupported_extension(&self) -> Option<&str> {
+        let extension = self.path.as_deref()?.extension()?.to_str()?;
+
+        (!language::is_supported(extension) && self.language() == language::PLAIN_TEXT)
+            .then_some(extension)
+    }
+}
+
+/// Picks the project root.
+async fn pick_folder() -> Result<PathBuf, EditorError> {
+    Ok(AsyncFileDialog::new()
+        .set_title("Choose the project root...")
+        .pick_folder()
+        .await
+        .ok_or(EditorError::PickFileError)?
+        .path()
+        .to_path_buf())
+}
+
+/// Picks any number of files, starting in the folder if given, and loads them
+/// all, keeping the paths of those that fail.
+async fn pick_files(
+    folder: Option<PathBuf>,
+    timeout: Option<Duration>,
+) -> Result<Vec<Result<LoadedFile, PathBuf>>, EditorError> {
+    let dialog = AsyncFileDialog::new();
+    let dialog = match folder {
+        Some(folder) => dialog.set_directory(folder),
+        None => dialog,
+    };
+
+    let handles = dialog
+        .pick_files()
+        .await
+        .ok_or(EditorError::PickFileError)?;
+
+    Ok(
+        future::join_all(handles.into_iter().map(|handle| async move {
+            let path = handle.path().to_owned();
+
+            load_file(path.clone(), timeout).await.map_err(|_| path)
+        }))
+        .await,
+    )
+}
+
+async fn pick_tail(size: u64) -> Result<(PathBuf, tail::Chunk), EditorError> {
+    let handle = AsyncFileDialog::new()
+        .set_title("Open tail of...")
+        .pick_file()
+        .await
+        .ok_or(EditorError::PickFileError)?;
+
+    let path = handle.path().to_owned();
+    let chunk = tail::read_before(&path, None, size).await?;
+
+    Ok((path, chunk))
+}
+
+/// Whether the file can be written by this user. Permission bits alone
+/// can't tell (they don't say whose they are, and ACLs or a read-only mount
+/// override them), so it's opened for writing, without truncating, to see.
+async fn is_writable(path: PathBuf) -> (PathBuf, bool) {
+    let is_writable = !matches!(
+        fs::OpenOptions::new().write(true).open(&path).await,
+        Err(err) if matches!(
+            err.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        )
+    );
+
+    (path, is_writable)
+}
+
+/// Reads the file, giving up after the timeout (if any) so a hung network
+/// mount doesn't stall the open forever.
+async fn load_file(
+    path: PathBuf,
+    timeout: Option<Duration>,
+) -> Result<(PathBuf, Arc<String>), EditorError> {
+    let contents = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, fs::read_to_string(&path))
+            .await
+            .map_err(|_| EditorError::Timeout(path.clone()))?,
+        None => fs::read_to_string(&path).await,
+    };
+
+    // Reading a folder fails with an error that doesn't say why
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(_)
+            if fs::metadata(&path)
+                .await
+                .is_ok_and(|metadata| metadata.is_dir()) =>
+        {
+            return Err(EditorError::IsDirectory(path));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok((path, contents.into()))
+}
+
+/// The name a template is listed under: its file name, extension included,
+/// since that's what tells templates of different languages apart.
+fn template_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+async fn pick_save_path() -> Result<PathBuf, EditorError> {
+    Ok(AsyncFileDialog::new()
+        .set_title("Choose a name...")
+        .save_file()
+        .await
+        .ok_or(EditorError::PickFileError)?
+        .path()
+        .to_owned())
+}
+
+async fn save_file(path: Option<PathBuf>, contents: String) -> Result<PathBuf, EditorError> {
+    let path = match path {
+        Some(path) => path,
+        None => pick_save_path().await?,
+    };
+
+    fs::write(&path, contents)
+        .await
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::PermissionDenied => EditorError::PermissionDenied(path.clone()),
+            _ => err.into(),
+        })?;
+
+    Ok(path)
+}
+
+/// Hashes the file at the path, or the contents when there is none, off the
+/// UI thread.
+async fn compute_checksum(
+    algorithm: checksum::Algorithm,
+    path: Option<PathBuf>,
+    contents: String,
+) -> Result<checksum::Checksum, EditorError> {
+    let of_file = path.is_some();
+    let bytes = match path {
+        Some(path) => fs::read(path).await?,
+        None => contents.into_bytes(),
+    };
+
+    let hex = tokio::task::spawn_blocking(move || checksum::compute(algorithm, &bytes))
+        .await
+        .map_err(io::Error::other)?;
+
+    Ok(checksum::Checksum {
+        algorithm,
+        hex,
+        of_file,
+    })
+}
+
+/// Writes the contents to a newly picked file, leaving the buffer tied to its
+/// own.
+async fn save_copy(contents: String) -> Result<PathBuf, EditorError> {
+    let path = AsyncFileDialog::new()
+        .set_title("Save a copy as...")
+        .save_file()
+        .await
+        .ok_or(EditorError::PickFileError)?
+        .path()
+        .to_owned();
+
+    fs::write(&path, contents).await?;
+
+    Ok(path)
+}
+
+/// Writes the file through `pkexec tee`, which asks for an administrator's
+/// password. Only Linux has a way to do this.
+async fn save_elevated(path: PathBuf, contents: String) -> Result<PathBuf, EditorError> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new("pkexec")
+        .arg("tee")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(contents.as_bytes()).await?;
+    }
+
+    if child.wait().await?.success() {
+        Ok(path)
+    } else {
+        Err(EditorError::PermissionDenied(path))
+    }
+}
+
+async fn pick_comparison(timeout: Option<Duration>) -> Result<diff::Comparison, EditorError> {
+    let left = AsyncFileDialog::new()
+        .set_title("Compare...")
+        .pick_file()
+        .await
+        .ok_or(EditorError::PickFileError)?;
+
+    let right = AsyncFileDialog::new()
+        .set_title("...with")
+        .pick_file()
+        .await
+        .ok_or(EditorError::PickFileError)?;
+
+    let (left_path, left_contents) = load_file(left.path().to_owned(), timeout).await?;
+    let (right_path, right_contents) = load_file(right.path().to_owned(), timeout).await?;
+
+    Ok(diff::Comparison::new(
+        left_path,
+        &left_contents,
+        right_path,
+        &right_contents,
+    ))
+}
+
+fn content_hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn has_known_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(language::is_supported)
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_owned())
+}
+
+fn default_file() -> PathBuf {
+    PathBuf::from(format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")))
+}
+
+fn action<'a>(
+    content: Element<'a, Message>,
+    label: &'a str,
+    press: Option<Message>,
+) -> Element<'a, Message> {
+    let is_disabled = press.is_none();
+
+    tooltip(
+        button(container(content).width(30).center_x())
+            .on_press_maybe(press)
+            .padding([5, 10])
+            .style(if is_disabled {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Primary
+            }),
+        label,
+        tooltip::Position::FollowCursor,
+    )
+    .style(theme::Container::Box)
+    .into()
+}
+
+const DIFF_ROW_HEIGHT: f32 = 20.0;
+
+fn palette_input() -> text_input::Id {
+    text_input::Id::new("palette")
+}
+
+fn window_level(config: &Config) -> window::Level {
+    if config.always_on_top {
+        window::Level::AlwaysOnTop
+    } else {
+        window::Level::Normal
+    }
+}
+
+fn find_input() -> text_input::Id {
+    text_input::Id::new("find")
+}
+
+fn tag_input() -> text_input::Id {
+    text_input::Id::new("tag")
+}
+
+fn note_input() -> text_input::Id {
+    text_input::Id::new("note")
+}
+
+fn symbol_input() -> text_input::Id {
+    text_input::Id::new("symbols")
+}
+
+fn comparison_scrollable() -> scrollable::Id {
+    scrollable::Id::new("comparison")
+}
+
+fn outline_view(outline: &[symbols::Symbol]) -> Element<'_, Message> {
+    let headings: Element<'_, Message> = if outline.is_empty() {
+        text("No headings").size(14).into()
+    } else {
+        column(outline.iter().map(|heading| {
+            button(text(&heading.label).size(14))
+                .on_press(Message::JumpToSymbol(heading.line))
+                .width(iced::Length::Fill)
+                .style(theme::Button::Text)
+                .padding([2, 8, 2, 8 + heading.depth as u16 * 12])
+                .into()
+        }))
+        .into()
+    };
+
+    container(scrollable(headings))
+        .width(220)
+        .height(iced::Length::Fill)
+        .padding(5)
+        .style(theme::Container::Box)
+        .into()
+}
+
+fn comparison_view(comparison: &diff::Comparison) -> Element<'_, Message> {
+    let changes = match comparison.hunks.len() {
+        0 => String::from("Files are identical"),
+        1 => String::from("1 change"),
+        n => format!("{n} changes"),
+    };
+
+    let header = row![
+        text(format!(
+            "{} ↔ {}",
+            comparison.left_path.display(),
+            comparison.right_path.display()
+        ))
+        .size(14),
+        horizontal_space(),
+        text(changes).size(14),
+        button(
+            text(if comparison.ignore_whitespace {
+                "Show whitespace changes"
+            } else {
+                "Ignore whitespace changes"
+            })
+            .size(14),
+        )
+        .on_press(Message::ToggleIgnoreWhitespace)
+        .style(theme::Button::Secondary)
+        .padding([2, 8]),
+        button(text("Previous change").size(14))
+            .on_press(Message::PreviousHunk)
+            .padding([2, 8]),
+        button(text("Next change").size(14))
+            .on_press(Message::NextHunk)
+            .padding([2, 8]),
+        button(text("Close").size(14))
+            .on_press(Message::CloseComparison)
+            .style(theme::Button::Secondary)
+            .padding([2, 8]),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center);
+
+    let side = |line: &Option<(usize, String)>, kind: diff::RowKind| {
+        let background = match (line, kind) {
+            (_, diff::RowKind::Equal) => None,
+            (_, diff::RowKind::Whitespace) if comparison.ignore_whitespace => None,
+            (None, _) => Some(Color::from_rgba(0.5, 0.5, 0.5, 0.1)),
+            (Some(_), diff::RowKind::Whitespace) => Some(Color::from_rgba(0.3, 0.5, 0.9, 0.25)),
+            (Some(_), diff::RowKind::Removed) => Some(Color::from_rgba(0.9, 0.2, 0.2, 0.25)),
+            (Some(_), diff::RowKind::Added) => Some(Color::from_rgba(0.2, 0.8, 0.3, 0.25)),
+            (Some(_), diff::RowKind::Changed) => Some(Color::from_rgba(0.9, 0.7, 0.2, 0.25)),
+        };
+
+        let (number, contents) = match line {
+            Some((number, contents)) if kind == diff::RowKind::Whitespace => {
+                ((number + 1).to_string(), visible_whitespace(contents))
+            }
+            Some((number, contents)) => (
+                (number + 1).to_string(),
+                contents.trim_end_matches('\r').to_string(),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        container(row![
+            text(number).size(14).width(50),
+            text(contents).size(14)
+        ])
+        .width(iced::Length::Fill)
+        .height(DIFF_ROW_HEIGHT)
+        .clip(true)
+        .style(container::Appearance {
+            background: background.map(Background::Color),
+            ..container::Appearance::default()
+        })
+    };
+
+    let rows = column(comparison.rows.iter().map(|row| {
+        row![side(&row.left, row.kind), side(&row.right, row.kind)]
+            .spacing(10)
+            .into()
+    }));
+
+    column![
+        header,
+        scrollable(rows)
+            .id(comparison_scrollable())
+            .height(iced::Length::Fill)
+    ]
+    .spacing(10)
+    .padding(10)
+    .into()
+}
+
+/// The theme's text editor with its background let through by the window
+/// opacity.
+struct Translucent(f32);
+
+impl Translucent {
+    fn apply(&self, appearance: text_editor::Appearance) -> text_editor::Appearance {
+        let background = match appearance.background {
+            Background::Color(color) => Background::Color(Color {
+                a: color.a * self.0,
+                ..color
+            }),
+            background => background,
+        };
+
+        text_editor::Appearance {
+            background,
+            ..appearance
+        }
+    }
+}
+
+impl text_editor::StyleSheet for Translucent {
+    type Style = Theme;
+
+    fn active(&self, theme: &Theme) -> text_editor::Appearance {
+        self.apply(theme.active(&theme::TextEditor::Default))
+    }
+
+    fn focused(&self, theme: &Theme) -> text_editor::Appearance {
+        self.apply(theme.focused(&theme::TextEditor::Default))
+    }
+
+    fn hovered(&self, theme: &Theme) -> text_editor::Appearance {
+        self.apply(theme.hovered(&theme::TextEditor::Default))
+    }
+
+    fn disabled(&self, theme: &Theme) -> text_editor::Appearance {
+        self.apply(theme.disabled(&theme::TextEditor::Default))
+    }
+
+    fn placeholder_color(&self, theme: &Theme) -> Color {
+        theme.placeholder_color(&theme::TextEditor::Default)
+    }
+
+    fn value_color(&self, theme: &Theme) -> Color {
+        theme.value_color(&theme::TextEditor::Default)
+    }
+
+    fn disabled_color(&self, theme: &Theme) -> Color {
+        theme.disabled_color(&theme::TextEditor::Default)
+    }
+
+    fn selection_color(&self, theme: &Theme) -> Color {
+        theme.selection_color(&theme::TextEditor::Default)
+    }
+}
+
+fn toast_error_appearance(theme: &Theme) -> container::Appearance {
+    let danger = theme.palette().danger;
+
+    container::Appearance {
+        background: Some(Background::Color(Color { a: 0.2, ..danger })),
+        border: iced::Border {
+            color: danger,
+            width: 1.0,
+            radius: 4.0.into(),
+        },
+        ..container::Appearance::default()
+    }
+}
+
+/// The line with its spaces, tabs and `\r` ending drawn as symbols, so a
+/// whitespace-only change can be told apart.
+fn visible_whitespace(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            ' ' => '·',
+            '\t' => '→',
+            '\r' => '␍',
+            c => c,
+        })
+        .collect()
+}
+
+fn status_action<'a>(label: String, press: Message) -> Element<'a, Message> {
+    button(text(label).size(14))
+        .on_press(press)
+        .padding([2, 4])
+        .style(theme::Button::Text)
+        .into()
 }
 
 fn icon<'a>(endpoint: char) -> Element<'a, Message> {
@@ -0,0 +1,11 @@
+/// Whether a command label matches a palette query: every character of the
+/// query appears in the label, in order, ignoring case.
+pub fn matches(query: &str, label: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|c| label.any(|l| l == c))
+}
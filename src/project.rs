@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+/// The project a file belongs to: the closest folder above it holding one
+/// of the markers, like `.git` or `Cargo.toml`.
+pub async fn find_root(file: PathBuf, markers: Vec<String>) -> Option<PathBuf> {
+    for folder in file.ancestors().skip(1) {
+        for marker in &markers {
+            if fs::try_exists(folder.join(marker)).await.unwrap_or(false) {
+                return Some(folder.to_path_buf());
+            }
+        }
+    }
+
+    None
+}
+
+/// The name a project goes by: the name of its root folder.
+pub fn name(root: &Path) -> String {
+    root.file_name()
+        .unwrap_or(root.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,181 @@
+use std::{
+    fs, panic,
+    path::PathBuf,
+    process,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Session};
+
+/// Unsaved buffer contents, dumped to disk if the editor panics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+/// Written at startup and kept up to date while running, one per running
+/// editor; finding one whose editor is gone means it didn't exit cleanly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Sentinel {
+    pub session: Option<Session>,
+}
+
+/// The latest snapshot of the buffer, or `None` when there is nothing unsaved.
+pub type Handle = Arc<Mutex<Option<Snapshot>>>;
+
+/// Installs a panic hook writing the latest snapshot to the recovery file
+/// before running the default hook.
+pub fn install_hook(handle: Handle) {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        // A panic while the snapshot is being updated leaves it locked
+        if let Ok(snapshot) = handle.try_lock() {
+            if let Some(snapshot) = snapshot.as_ref() {
+                let _ = write(snapshot);
+            }
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// The snapshot left behind by a crash, if any, once [`mark_running`] has
+/// taken over the files of the editor that crashed.
+pub fn load() -> Option<Snapshot> {
+    let contents = fs::read_to_string(recovery_file()?).ok()?;
+
+    serde_json::from_str(&contents).ok()
+}
+
+/// Deletes the recovery file.
+pub fn discard() {
+    if let Some(path) = recovery_file() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Writes a fresh sentinel, returning the one left behind by an editor that
+/// didn't exit cleanly, if any.
+///
+/// Editors still running are left alone, so a second window doesn't report
+/// their buffers as crashed. The crashed editor's snapshot becomes this one's,
+/// for [`load`] to find and [`discard`] to remove.
+pub fn mark_running() -> Option<Sentinel> {
+    let dir = dir()?;
+    fs::create_dir_all(&dir).ok()?;
+
+    let crashed = fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            let pid = name
+                .to_str()?
+                .strip_prefix("running-")?
+                .strip_suffix(".json")?;
+
+            pid.parse::<u32>().ok()
+        })
+        .find(|&pid| pid != process::id() && !is_alive(pid));
+
+    let previous = crashed.map(|pid| {
+        let sentinel = fs::read_to_string(dir.join(sentinel_name(pid)))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        let _ = fs::remove_file(dir.join(sentinel_name(pid)));
+        let _ = fs::rename(
+            dir.join(recovery_name(pid)),
+            dir.join(recovery_name(process::id())),
+        );
+
+        sentinel
+    });
+
+    let _ = fs::write(
+        sentinel_file()?,
+        serde_json::to_string(&Sentinel::default()).ok()?,
+    );
+
+    previous
+}
+
+/// Records the session, and the unsaved buffer if there is one, so they
+/// survive a crash the panic hook never sees.
+pub async fn check_in(session: Option<Session>, snapshot: Option<Snapshot>) {
+    if let Some(path) = sentinel_file() {
+        let sentinel = Sentinel { session };
+
+        if let Ok(contents) = serde_json::to_string(&sentinel) {
+            let _ = tokio::fs::write(path, contents).await;
+        }
+    }
+
+    if let (Some(path), Some(snapshot)) = (recovery_file(), snapshot) {
+        if let Ok(contents) = serde_json::to_string(&snapshot) {
+            let _ = tokio::fs::write(path, contents).await;
+        }
+    }
+}
+
+/// Removes the sentinel and the recovery file on a clean exit.
+pub fn mark_exited() {
+    if let Some(path) = sentinel_file() {
+        let _ = fs::remove_file(path);
+    }
+
+    discard();
+}
+
+fn write(snapshot: &Snapshot) -> Option<()> {
+    let path = recovery_file()?;
+
+    fs::create_dir_all(path.parent()?).ok()?;
+    fs::write(path, serde_json::to_string(snapshot).ok()?).ok()
+}
+
+/// Whether the process is still running. Where that can't be told, it's
+/// taken to be, so nothing is ever recovered from under a running editor.
+fn is_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+
+        // Signal 0 checks the process exists without touching it; one owned
+        // by someone else still exists
+        let exists = unsafe { libc::kill(pid, 0) } == 0;
+
+        exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("recovery"))
+}
+
+fn recovery_name(pid: u32) -> String {
+    format!("recovery-{pid}.json")
+}
+
+fn sentinel_name(pid: u32) -> String {
+    format!("running-{pid}.json")
+}
+
+fn recovery_file() -> Option<PathBuf> {
+    dir().map(|dir| dir.join(recovery_name(process::id())))
+}
+
+fn sentinel_file() -> Option<PathBuf> {
+    dir().map(|dir| dir.join(sentinel_name(process::id())))
+}
@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+/// Which way Next and Previous look from the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// A match of the query, as a byte range of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub range: Range<usize>,
+}
+
+/// Finds the closest match of the query from the position (a line and byte
+/// column), going around the end of the text if there's none before it.
+/// Also says whether it had to go around.
+///
+/// A query without capitals ignores case, as most searches are typed in
+/// lowercase.
+pub fn find(
+    text: &str,
+    query: &str,
+    (line, column): (usize, usize),
+    direction: Direction,
+) -> Option<(Match, bool)> {
+    let matches = matches(text, query);
+
+    let found = match direction {
+        Direction::Forward => matches
+            .iter()
+            .find(|found| (found.line, found.range.start) >= (line, column))
+            .map(|found| (found, false))
+            .or_else(|| matches.first().map(|found| (found, true))),
+        Direction::Backward => matches
+            .iter()
+            .rev()
+            .find(|found| (found.line, found.range.start) < (line, column))
+            .map(|found| (found, false))
+            .or_else(|| matches.last().map(|found| (found, true))),
+    };
+
+    found.map(|(found, wrapped)| (found.clone(), wrapped))
+}
+
+/// Every match of the query in the text, in order; matches don't span lines.
+pub fn matches(text: &str, query: &str) -> Vec<Match> {
+    let Some((query, ignore_case)) = fold(query) else {
+        return Vec::new();
+    };
+
+    text.lines()
+        .enumerate()
+        .flat_map(|(line, contents)| {
+            line_matches(contents, &query, ignore_case)
+                .into_iter()
+                .map(move |range| Match { line, range })
+        })
+        .collect()
+}
+
+/// The query as it's matched, and whether case is ignored, unless it can't
+/// match anything.
+pub fn fold(query: &str) -> Option<(String, bool)> {
+    if query.is_empty() || query.contains('\n') {
+        return None;
+    }
+
+    // ASCII case folding keeps the byte offsets of the original line
+    let ignore_case = !query.chars().any(char::is_uppercase);
+
+    Some(if ignore_case {
+        (query.to_ascii_lowercase(), true)
+    } else {
+        (query.to_string(), false)
+    })
+}
+
+/// The byte ranges of the matches of a folded query in one line.
+pub fn line_matches(line: &str, query: &str, ignore_case: bool) -> Vec<Range<usize>> {
+    let line = if ignore_case {
+        line.to_ascii_lowercase()
+    } else {
+        line.to_string()
+    };
+
+    line.match_indices(query)
+        .map(|(start, found)| start..start + found.len())
+        .collect()
+}
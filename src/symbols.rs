@@ -0,0 +1,222 @@
+use std::iter;
+
+use crate::language;
+
+/// A definition or heading found in a file, to jump to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub line: usize,
+    pub label: String,
+    /// How deeply it's nested: the heading level minus one in Markdown, and
+    /// whether it's indented in code.
+    pub depth: usize,
+}
+
+/// Finds the symbols of a file by looking at how its lines start, which is
+/// enough to navigate by without parsing the language.
+pub fn find(text: &str, token: &str) -> Vec<Symbol> {
+    if language::name(token) == "Markdown" {
+        return headings(text);
+    }
+
+    let Some(keywords) = keywords(token) else {
+        return Vec::new();
+    };
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, contents)| {
+            let trimmed = contents.trim_start();
+            let definition = strip_modifiers(trimmed);
+
+            keywords
+                .iter()
+                .any(|keyword| definition.starts_with(keyword))
+                .then(|| Symbol {
+                    line,
+                    label: label(trimmed),
+                    depth: usize::from(trimmed.len() < contents.len()),
+                })
+        })
+        .collect()
+}
+
+/// The lines that look like they declare the name: a definition keyword or
+/// a variable binding right ahead of it. Languages without keywords of their
+/// own get those of all of them.
+pub fn definitions(text: &str, token: &str, name: &str) -> Vec<usize> {
+    const BINDINGS: &[&str] = &[
+        "let mut ",
+        "let ",
+        "const ",
+        "static mut ",
+        "static ",
+        "var ",
+        "val ",
+    ];
+    const ANY: &[&str] = &[
+        "fn ",
+        "func ",
+        "function ",
+        "def ",
+        "struct ",
+        "enum ",
+        "trait ",
+        "class ",
+        "type ",
+        "interface ",
+        "module ",
+        "mod ",
+    ];
+
+    let keywords = keywords(token).unwrap_or(ANY);
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, contents)| {
+            // `const` and `static` are modifiers ahead of some keywords and
+            // bindings of their own, so the line is tried with each modifier
+            // stripped in turn
+            let mut definitions =
+                iter::successors(Some(contents.trim_start()), |line| strip_modifier(line));
+
+            definitions.any(|definition| {
+                keywords.iter().chain(BINDINGS).any(|keyword| {
+                    definition
+                        .strip_prefix(keyword)
+                        .and_then(|rest| rest.trim_start().strip_prefix(name))
+                        .is_some_and(|rest| {
+                            !rest
+                                .chars()
+                                .next()
+                                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                        })
+                })
+            })
+        })
+        .map(|(line, _)| line)
+        .collect()
+}
+
+/// The keywords that start definitions in the language, if it's one whose
+/// definitions are known.
+fn keywords(token: &str) -> Option<&'static [&'static str]> {
+    Some(match language::name(token) {
+        "Rust" => &[
+            "fn ",
+            "struct ",
+            "enum ",
+            "trait ",
+            "impl ",
+            "impl<",
+            "mod ",
+            "type ",
+            "union ",
+            "macro_rules!",
+        ],
+        "Python" => &["def ", "class "],
+        "JavaScript" | "TypeScript" => &["function ", "function* ", "class ", "interface "],
+        "Go" => &["func ", "type "],
+        "Ruby" => &["def ", "class ", "module "],
+        "Java" | "C#" => &["class ", "interface ", "enum ", "record "],
+        _ => return None,
+    })
+}
+
+/// The headings of a Markdown document, skipping fenced code blocks.
+fn headings(text: &str) -> Vec<Symbol> {
+    let mut is_in_code = false;
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, contents)| {
+            let trimmed = contents.trim_start();
+
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                is_in_code = !is_in_code;
+            }
+
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let title = &trimmed[level..];
+
+            (!is_in_code && (1..=6).contains(&level) && title.starts_with(' ')).then(|| Symbol {
+                line,
+                label: title.trim().trim_end_matches('#').trim_end().to_string(),
+                depth: level - 1,
+            })
+        })
+        .collect()
+}
+
+/// The line without the visibility and other modifiers that can come ahead
+/// of the keyword of a definition.
+fn strip_modifiers(mut line: &str) -> &str {
+    while let Some(rest) = strip_modifier(line) {
+        line = rest;
+    }
+
+    line
+}
+
+/// The line without the modifier it starts with, if it does.
+fn strip_modifier(line: &str) -> Option<&str> {
+    const MODIFIERS: &[&str] = &[
+        "pub(crate) ",
+        "pub(super) ",
+        "pub ",
+        "export default ",
+        "export ",
+        "async ",
+        "unsafe ",
+        "const ",
+        "extern \"C\" ",
+        "default ",
+        "public ",
+        "private ",
+        "protected ",
+        "internal ",
+        "static ",
+        "abstract ",
+        "final ",
+        "sealed ",
+        "partial ",
+    ];
+
+    MODIFIERS
+        .iter()
+        .find_map(|modifier| line.strip_prefix(modifier))
+}
+
+/// The definition up to its body, as shown in the symbol list.
+fn label(line: &str) -> String {
+    let end = line.find(['{', ';']).unwrap_or(line.len());
+
+    line[..end].trim_end().trim_end_matches(':').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_finds_const_and_static_items() {
+        let text = "const MAX: usize = 4;\n\
+                    pub static NAME: &str = \"a\";\n\
+                    static mut COUNT: u32 = 0;\n\
+                    pub(crate) const fn max() -> usize { MAX }\n\
+                    let x = MAX;";
+
+        assert_eq!(definitions(text, "rs", "MAX"), [0]);
+        assert_eq!(definitions(text, "rs", "NAME"), [1]);
+        assert_eq!(definitions(text, "rs", "COUNT"), [2]);
+        assert_eq!(definitions(text, "rs", "max"), [3]);
+    }
+
+    #[test]
+    fn definitions_finds_const_bindings_in_other_languages() {
+        assert_eq!(
+            definitions("export const answer = 42;", "js", "answer"),
+            [0]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::{io::SeekFrom, path::Path};
+
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::{error::EditorError, format};
+
+/// A run of whole lines read from the end of a file backwards.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// The byte offset in the file where the text starts.
+    pub start: u64,
+    /// The byte offset in the file where the text ends.
+    pub end: u64,
+    pub text: String,
+}
+
+/// Reads up to `size` bytes ending at `end` (the end of the file if `None`).
+///
+/// The partial line at the start of the read is dropped, so it can be picked
+/// up whole by the next chunk, unless that would leave nothing at all.
+pub async fn read_before(path: &Path, end: Option<u64>, size: u64) -> Result<Chunk, EditorError> {
+    let mut file = File::open(path).await?;
+    let end = match end {
+        Some(end) => end,
+        None => file.metadata().await?.len(),
+    };
+    let start = end.saturating_sub(size);
+
+    file.seek(SeekFrom::Start(start)).await?;
+
+    let mut bytes = vec![0; (end - start) as usize];
+    file.read_exact(&mut bytes).await?;
+
+    let skip = if start > 0 {
+        partial_line_len(&bytes)
+    } else {
+        0
+    };
+
+    Ok(Chunk {
+        start: start + skip as u64,
+        end,
+        text: format::normalize_line_endings(&String::from_utf8_lossy(&bytes[skip..])),
+    })
+}
+
+/// How many bytes at the start of a read from the middle of a file belong to
+/// a line that started before it: up to the first newline, unless nothing
+/// would be left after it. Without a newline to go by, only the tail of a
+/// character split by the read is dropped, so it's read whole with the bytes
+/// before.
+fn partial_line_len(bytes: &[u8]) -> usize {
+    let skip = match bytes.iter().position(|&byte| byte == b'\n') {
+        Some(newline) if newline + 1 < bytes.len() => newline + 1,
+        _ => 0,
+    };
+
+    skip + bytes[skip..]
+        .iter()
+        .take_while(|&&byte| (0x80..=0xbf).contains(&byte))
+        .count()
+}
+
+/// Reads the whole lines added past `start` since the file was last read,
+/// or the entire file if it has shrunk below `start` (say, it was rotated).
+pub async fn read_after(path: &Path, start: u64) -> Result<Option<Chunk>, EditorError> {
+    let mut file = File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let start = if len < start { 0 } else { start };
+
+    file.seek(SeekFrom::Start(start)).await?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).await?;
+
+    // A line still being written is picked up once it's finished
+    let Some(newline) = bytes.iter().rposition(|&byte| byte == b'\n') else {
+        return Ok((start == 0).then(|| Chunk {
+            start,
+            end: start,
+            text: String::new(),
+        }));
+    };
+
+    bytes.truncate(newline + 1);
+
+    Ok(Some(Chunk {
+        start,
+        end: start + bytes.len() as u64,
+        text: format::normalize_line_endings(&String::from_utf8_lossy(&bytes)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_line_len_drops_the_line_cut_by_the_read() {
+        assert_eq!(partial_line_len(b"tail\nwhole\n"), 5);
+    }
+
+    #[test]
+    fn partial_line_len_keeps_a_read_ending_in_its_only_newline() {
+        assert_eq!(partial_line_len(b"only a tail\n"), 0);
+    }
+
+    #[test]
+    fn partial_line_len_skips_a_split_character_without_a_newline() {
+        // The last two bytes of "é" (0xc3 0xa9) and "€" (0xe2 0x82 0xac)
+        assert_eq!(partial_line_len(&[0xa9, b'a', b'b']), 1);
+        assert_eq!(partial_line_len(&[0x82, 0xac, b'a']), 2);
+
+        let text = "a€b".as_bytes();
+        let rest = &text[2..];
+
+        assert_eq!(
+            String::from_utf8_lossy(&rest[partial_line_len(rest)..]),
+            "b"
+        );
+    }
+
+    #[test]
+    fn partial_line_len_keeps_a_read_starting_on_a_character() {
+        assert_eq!(partial_line_len("éa".as_bytes()), 0);
+        assert_eq!(partial_line_len(b"abc"), 0);
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use crate::config;
+
+/// The directory users keep templates in, one file each, named like the
+/// files they start.
+pub fn dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("templates"))
+}
+
+/// The template files, sorted by name; none if the directory is missing.
+pub async fn list() -> Vec<PathBuf> {
+    let Some(dir) = dir() else {
+        return Vec::new();
+    };
+
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return Vec::new();
+    };
+
+    let mut templates = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.file_type().await.is_ok_and(|kind| kind.is_file()) {
+            templates.push(entry.path());
+        }
+    }
+
+    templates.sort();
+    templates
+}
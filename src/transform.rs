@@ -0,0 +1,700 @@
+use std::ops::Range;
+
+use base64::Engine;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+use crate::language::BlockComment;
+
+/// Cleans up text copied from rich sources: non-breaking spaces become plain
+/// spaces, line endings become `\n` and zero-width characters are dropped.
+pub fn plain_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .filter_map(|c| match c {
+            '\r' => Some('\n'),
+            '\u{a0}' | '\u{2007}' | '\u{202f}' => Some(' '),
+            '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}' => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// A character that doesn't show up (or looks like a plain space) in the
+/// editor, found at a line and byte column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Invisible {
+    pub line: usize,
+    pub column: usize,
+    pub character: char,
+}
+
+/// Finds every control, zero-width/format and unusual whitespace character.
+///
+/// A byte order mark at the very start of the text is left alone.
+pub fn find_invisible(text: &str) -> Vec<Invisible> {
+    text.split('\n')
+        .enumerate()
+        .flat_map(|(line, contents)| {
+            contents
+                .char_indices()
+                .filter(move |&(column, c)| {
+                    is_invisible(c) && !(line == 0 && column == 0 && c == '\u{feff}')
+                })
+                .map(move |(column, character)| Invisible {
+                    line,
+                    column,
+                    character,
+                })
+        })
+        .collect()
+}
+
+/// Removes every character [`find_invisible`] reports, turning unusual
+/// whitespace into plain spaces.
+pub fn strip_invisible(text: &str) -> String {
+    text.char_indices()
+        .filter_map(|(i, c)| match c {
+            '\u{feff}' if i == 0 => Some(c),
+            c if is_invisible(c) && c.is_whitespace() => Some(' '),
+            c if is_invisible(c) => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+fn is_invisible(c: char) -> bool {
+    match c {
+        '\t' | '\n' | '\r' | ' ' => false,
+        c if c.is_control() => true,
+        c if c.is_whitespace() => true,
+        // Unicode format characters (category Cf)
+        '\u{ad}'
+        | '\u{600}'..='\u{605}'
+        | '\u{61c}'
+        | '\u{6dd}'
+        | '\u{70f}'
+        | '\u{180e}'
+        | '\u{200b}'..='\u{200f}'
+        | '\u{202a}'..='\u{202e}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{206f}'
+        | '\u{feff}'
+        | '\u{fff9}'..='\u{fffb}' => true,
+        _ => false,
+    }
+}
+
+/// Wraps the text in a block comment, or unwraps it if it is one already.
+/// Whitespace around the text stays outside the delimiters.
+///
+/// Fails when wrapping would end the comment early, in languages whose
+/// comments don't nest.
+pub fn toggle_block_comment(text: &str, comment: BlockComment) -> Result<String, &'static str> {
+    let core = text.trim();
+    let start = text.len() - text.trim_start().len();
+    let (leading, trailing) = (&text[..start], &text[start + core.len()..]);
+
+    let inner = core
+        .strip_prefix(comment.open)
+        .and_then(|core| core.strip_suffix(comment.close))
+        .filter(|_| core.len() >= comment.open.len() + comment.close.len());
+
+    let core = match inner {
+        Some(inner) => {
+            let inner = inner.strip_prefix(' ').unwrap_or(inner);
+
+            inner.strip_suffix(' ').unwrap_or(inner).to_string()
+        }
+        None if !comment.nests && core.contains(comment.close) => {
+            return Err("The selection already contains the end of a comment");
+        }
+        None => format!("{} {core} {}", comment.open, comment.close),
+    };
+
+    Ok(format!("{leading}{core}{trailing}"))
+}
+
+/// Collapses every run of blank (or whitespace-only) lines down to its first
+/// line, returning the text and how many lines were removed.
+pub fn squeeze_blank_lines(text: &str) -> (String, usize) {
+    let mut removed = 0;
+    let mut after_blank = false;
+
+    let lines: Vec<&str> = text
+        .split('\n')
+        .filter(|line| {
+            let is_blank = line.trim().is_empty();
+            let keep = !(is_blank && after_blank);
+
+            after_blank = is_blank;
+            removed += usize::from(!keep);
+            keep
+        })
+        .collect();
+
+    (lines.join("\n"), removed)
+}
+
+/// Which way "Normalize punctuation" converts quotes and dashes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Punctuation {
+    /// Curly quotes become straight ones and em/en dashes become hyphens, as
+    /// code and plain text want.
+    #[default]
+    Straight,
+    /// Straight quotes curl according to where they stand, `---` becomes an
+    /// em dash and `--` an en dash, as prose wants.
+    Smart,
+}
+
+pub fn normalize_punctuation(text: &str, punctuation: Punctuation) -> String {
+    match punctuation {
+        Punctuation::Straight => text
+            .chars()
+            .map(|c| match c {
+                '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' => '"',
+                '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' => '\'',
+                '\u{2013}' | '\u{2014}' => '-',
+                c => c,
+            })
+            .collect(),
+        Punctuation::Smart => {
+            let text = text.replace("---", "\u{2014}").replace("--", "\u{2013}");
+            let mut previous = None;
+
+            text.chars()
+                .map(|c| {
+                    // A quote opens after a space, an opening bracket or
+                    // another opening quote, and closes everywhere else
+                    let opens = previous.is_none_or(|previous: char| {
+                        previous.is_whitespace()
+                            || "([{\u{2014}\u{2013}\u{201c}\u{2018}".contains(previous)
+                    });
+
+                    let c = match c {
+                        '"' if opens => '\u{201c}',
+                        '"' => '\u{201d}',
+                        '\'' if opens => '\u{2018}',
+                        '\'' => '\u{2019}',
+                        c => c,
+                    };
+
+                    previous = Some(c);
+                    c
+                })
+                .collect()
+        }
+    }
+}
+
+/// An encoding selections can be converted to and from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeKind {
+    Base64,
+    /// Percent-encoding, with everything but `A-Z a-z 0-9 - . _ ~` escaped.
+    Url,
+}
+
+/// Characters left as they are by URL encoding, besides letters and digits.
+const URL_SAFE: &percent_encoding::AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+pub fn encode(text: &str, kind: EncodeKind) -> String {
+    match kind {
+        EncodeKind::Base64 => base64::engine::general_purpose::STANDARD.encode(text),
+        EncodeKind::Url => utf8_percent_encode(text, URL_SAFE).to_string(),
+    }
+}
+
+/// Fails when the text isn't valid in the encoding, or doesn't decode to
+/// UTF-8 text.
+pub fn decode(text: &str, kind: EncodeKind) -> Result<String, &'static str> {
+    let bytes = match kind {
+        EncodeKind::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(text.trim())
+            .map_err(|_| "The selection isn't valid Base64")?,
+        EncodeKind::Url => {
+            let is_valid = text.split('%').skip(1).all(|escape| {
+                escape.len() >= 2 && escape.as_bytes()[..2].iter().all(u8::is_ascii_hexdigit)
+            });
+
+            if !is_valid {
+                return Err("The selection isn't valid percent-encoding");
+            }
+
+            percent_decode_str(text).collect()
+        }
+    };
+
+    String::from_utf8(bytes).map_err(|_| "The decoded selection isn't UTF-8 text")
+}
+
+/// Pretty-prints JSON with the given number of spaces per level, keeping the
+/// order of object keys.
+pub fn format_json(text: &str, indent: usize) -> Result<String, String> {
+    let value = parse_json(text)?;
+    let indent = " ".repeat(indent);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+
+    let mut output = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+
+    value
+        .serialize(&mut serializer)
+        .map_err(|err| err.to_string())?;
+
+    Ok(String::from_utf8(output).expect("JSON is UTF-8"))
+}
+
+pub fn minify_json(text: &str) -> Result<String, String> {
+    Ok(parse_json(text)?.to_string())
+}
+
+fn parse_json(text: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(text).map_err(|err| {
+        format!(
+            "Invalid JSON at line {}, column {}",
+            err.line(),
+            err.column()
+        )
+    })
+}
+
+/// The byte range of the number under or after the column in the line, with
+/// its minus sign but not a `0x` prefix.
+pub fn find_number(line: &str, column: usize) -> Option<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let is_hex = bytes[start] == b'0'
+            && matches!(bytes.get(start + 1), Some(b'x' | b'X'))
+            && bytes.get(start + 2).is_some_and(u8::is_ascii_hexdigit);
+
+        let (digits, is_digit): (usize, fn(&u8) -> bool) = if is_hex {
+            (start + 2, u8::is_ascii_hexdigit)
+        } else if bytes[start].is_ascii_digit() {
+            (start, u8::is_ascii_digit)
+        } else {
+            start += 1;
+            continue;
+        };
+
+        let end = digits
+            + bytes[digits..]
+                .iter()
+                .take_while(|&byte| is_digit(byte))
+                .count();
+
+        if end > column {
+            // A minus counts unless it's joining words, as in `utf-8`
+            let is_negative = !is_hex
+                && start > 0
+                && bytes[start - 1] == b'-'
+                && !(start > 1
+                    && (bytes[start - 2].is_ascii_alphanumeric() || bytes[start - 2] == b'_'));
+
+            return Some(if is_negative { start - 1 } else { start }..end);
+        }
+
+        start = end;
+    }
+
+    None
+}
+
+/// Adds `delta` to a number found by [`find_number`], keeping its base, the
+/// case of its hex digits and the width of zero padding, or `None` if the
+/// result doesn't fit.
+pub fn add_to_number(number: &str, delta: i64) -> Option<String> {
+    if let Some(digits) = number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        let value = u64::from_str_radix(digits, 16).ok()?;
+        let value = value.checked_add_signed(delta)?;
+        let width = digits.len();
+        let prefix = &number[..2];
+
+        return Some(if digits.chars().any(|c| c.is_ascii_uppercase()) {
+            format!("{prefix}{value:0width$X}")
+        } else {
+            format!("{prefix}{value:0width$x}")
+        });
+    }
+
+    let value = number.parse::<i64>().ok()?.checked_add(delta)?;
+    let digits = number.trim_start_matches('-');
+
+    // Only padded numbers keep their width, so 9 becomes 10 rather than 0 or 1
+    let width = if digits.len() > 1 && digits.starts_with('0') {
+        digits.len()
+    } else {
+        0
+    };
+
+    let sign = if value < 0 { "-" } else { "" };
+
+    Some(format!("{sign}{:0width$}", value.unsigned_abs()))
+}
+
+/// Adds `delta` to the first number on each line that has one, then twice
+/// `delta` to the next and so on, turning copies of a line into a sequence.
+pub fn step_numbers(text: &str, delta: i64) -> String {
+    let mut step = 0;
+
+    text.split('\n')
+        .map(|line| {
+            let Some(range) = find_number(line, 0) else {
+                return line.to_string();
+            };
+
+            step += 1;
+
+            match add_to_number(&line[range.clone()], delta.saturating_mul(step)) {
+                Some(number) => format!("{}{number}{}", &line[..range.start], &line[range.end..]),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What "Align columns" splits lines into columns at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// Runs of whitespace, as in plain-text tables.
+    Spaces,
+    /// Every occurrence of the character, as in `|` tables or CSV.
+    Every(char),
+    /// Only the first occurrence of the character, as in `=` assignments.
+    First(char),
+}
+
+/// Pads the columns of the lines so they line up, keeping each line's
+/// indentation. Lines with a single column are left alone.
+pub fn align_columns(text: &str, separator: Separator) -> String {
+    let rows: Vec<(&str, Vec<&str>)> = text
+        .split('\n')
+        .map(|line| {
+            let content = line.trim_start();
+            let indentation = &line[..line.len() - content.len()];
+
+            let cells = match separator {
+                Separator::Spaces => content.split_whitespace().collect(),
+                Separator::Every(c) => content.split(c).map(str::trim).collect(),
+                Separator::First(c) => content.split_once(c).map_or_else(
+                    || vec![content],
+                    |(left, right)| vec![left.trim(), right.trim()],
+                ),
+            };
+
+            (indentation, cells)
+        })
+        .collect();
+
+    let mut widths = Vec::new();
+
+    for (_, cells) in rows.iter().filter(|(_, cells)| cells.len() > 1) {
+        widths.resize(widths.len().max(cells.len()), 0);
+
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let gap = match separator {
+        Separator::Spaces => String::from(" "),
+        Separator::Every(c) | Separator::First(c) => format!(" {c} "),
+    };
+
+    rows.iter()
+        .zip(text.split('\n'))
+        .map(|((indentation, cells), line)| {
+            if cells.len() < 2 {
+                return line.to_string();
+            }
+
+            let last = cells.len() - 1;
+            let aligned = cells
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (cell, &width))| {
+                    if i == last {
+                        cell.to_string()
+                    } else {
+                        format!("{cell}{}", " ".repeat(width - cell.chars().count()))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(&gap);
+
+            // An empty first cell, as in `| a | b |`, would leave a space
+            // ahead of the separator
+            format!("{indentation}{}", aligned.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rewraps each paragraph of the text to lines of at most `width` characters,
+/// collapsing the spaces between words. Indentation, list markers and the
+/// `//`, `#` or `>` that start comment and quote lines carry over to the
+/// wrapped lines; a word longer than the width gets a line of its own.
+pub fn reflow(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    let mut paragraph: Option<(String, String, Vec<&str>)> = None;
+
+    let finish = |paragraph: Option<(String, String, Vec<&str>)>, lines: &mut Vec<String>| {
+        if let Some((first, rest, words)) = paragraph {
+            fill(&first, &rest, &words, width, lines);
+        }
+    };
+
+    for line in text.split('\n') {
+        let (prefix, contents) = split_line_prefix(line);
+        let marker_len = list_marker_len(contents);
+
+        let continues = paragraph
+            .as_ref()
+            .is_some_and(|(_, rest, _)| rest.trim_end() == prefix.trim_end());
+
+        if contents.trim().is_empty() {
+            finish(paragraph.take(), &mut lines);
+            lines.push(line.trim_end().to_string());
+        } else if continues && marker_len == 0 {
+            if let Some((_, _, words)) = &mut paragraph {
+                words.extend(contents.split_whitespace());
+            }
+        } else {
+            finish(paragraph.take(), &mut lines);
+
+            let (marker, words) = contents.split_at(marker_len);
+
+            paragraph = Some((
+                format!("{prefix}{marker}"),
+                format!("{prefix}{}", " ".repeat(marker.chars().count())),
+                words.split_whitespace().collect(),
+            ));
+        }
+    }
+
+    finish(paragraph, &mut lines);
+
+    lines.join("\n")
+}
+
+/// The line range of the paragraph around a line: the lines up to the blank
+/// ones around it.
+pub fn paragraph_at(text: &str, line: usize) -> Option<Range<usize>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let is_blank = |line: &&str| split_line_prefix(line).1.trim().is_empty();
+
+    if lines.get(line).is_none_or(is_blank) {
+        return None;
+    }
+
+    let start = lines[..line]
+        .iter()
+        .rposition(is_blank)
+        .map_or(0, |blank| blank + 1);
+    let end = lines[line..]
+        .iter()
+        .position(is_blank)
+        .map_or(lines.len(), |blank| line + blank);
+
+    Some(start..end)
+}
+
+/// Fills lines with the words, starting the first with `first` and the rest
+/// with `rest`.
+fn fill(first: &str, rest: &str, words: &[&str], width: usize, lines: &mut Vec<String>) {
+    let mut line = first.to_string();
+    let mut is_empty = true;
+
+    for word in words {
+        let length = line.chars().count() + usize::from(!is_empty) + word.chars().count();
+
+        if !is_empty && length > width {
+            lines.push(line);
+            line = rest.to_string();
+            is_empty = true;
+        }
+
+        if !is_empty {
+            line.push(' ');
+        }
+
+        line.push_str(word);
+        is_empty = false;
+    }
+
+    lines.push(line.trim_end().to_string());
+}
+
+/// Splits a line into the indentation and comment or quote marker that wrapped
+/// lines repeat, and what follows.
+fn split_line_prefix(line: &str) -> (&str, &str) {
+    const MARKERS: &[&str] = &["///", "//!", "//", "#", ">", "--", ";;"];
+
+    let indentation = line.len() - line.trim_start().len();
+    let rest = &line[indentation..];
+
+    let marker = MARKERS
+        .iter()
+        .find(|marker| rest.starts_with(*marker))
+        .map_or(0, |marker| {
+            let after = &rest[marker.len()..];
+
+            marker.len() + (after.len() - after.trim_start().len()).min(1)
+        });
+
+    line.split_at(indentation + marker)
+}
+
+/// The length of the list marker (`- `, `* `, `+ `, `1. ` or `1) `) the text
+/// starts with, if any.
+fn list_marker_len(text: &str) -> usize {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+
+    let marker = if digits > 0 {
+        text[digits..].starts_with(['.', ')']).then_some(digits + 1)
+    } else {
+        text.starts_with(['-', '*', '+']).then_some(1)
+    };
+
+    match marker {
+        Some(length) if text[length..].starts_with(' ') => length + 1,
+        _ => 0,
+    }
+}
+
+/// Swaps the characters on either side of a byte column of the line, or the
+/// last two at its end, returning the range rewritten and what replaces it.
+pub fn transpose_chars(line: &str, column: usize) -> Option<(Range<usize>, String)> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let at = chars.iter().position(|&(index, _)| index >= column);
+
+    let pair = match at {
+        Some(0) => return None,
+        Some(at) => at - 1,
+        None => chars.len().checked_sub(2)?,
+    };
+
+    let (start, first) = chars[pair];
+    let (_, second) = chars[pair + 1];
+
+    Some((
+        start..start + first.len_utf8() + second.len_utf8(),
+        format!("{second}{first}"),
+    ))
+}
+
+/// Swaps the word at (or before) a byte column of the line with the one
+/// after it, like the last two words at the end of the line, returning the
+/// range rewritten and what replaces it.
+pub fn transpose_words(line: &str, column: usize) -> Option<(Range<usize>, String)> {
+    let mut words: Vec<Range<usize>> = Vec::new();
+
+    for (index, c) in line.char_indices() {
+        let is_word = c.is_alphanumeric() || c == '_';
+
+        match words.last_mut() {
+            Some(word) if is_word && word.end == index => word.end = index + c.len_utf8(),
+            _ if is_word => words.push(index..index + c.len_utf8()),
+            _ => {}
+        }
+    }
+
+    let first = match words
+        .iter()
+        .position(|word| word.start < column && column < word.end)
+    {
+        Some(inside) => inside,
+        None => match words.iter().position(|word| word.start >= column) {
+            Some(next) => next.checked_sub(1)?,
+            None => words.len().checked_sub(2)?,
+        },
+    };
+
+    let (first, second) = (words.get(first)?, words.get(first + 1)?);
+
+    Some((
+        first.start..second.end,
+        format!(
+            "{}{}{}",
+            &line[second.clone()],
+            &line[first.end..second.start],
+            &line[first.clone()]
+        ),
+    ))
+}
+
+/// Sorts the lines from shortest to longest (or the other way around),
+/// keeping the order of lines of the same length.
+pub fn sort_by_length(text: &str, longest_first: bool) -> String {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+
+    // Reversing the shortest-first order would flip lines of the same
+    // length too
+    if longest_first {
+        lines.sort_by_key(|line| std::cmp::Reverse(line.chars().count()));
+    } else {
+        lines.sort_by_key(|line| line.chars().count());
+    }
+
+    lines.join("\n")
+}
+
+/// Puts the lines in a random order, picked by the seed.
+pub fn shuffle_lines(text: &str, seed: u64) -> String {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+
+    fastrand::Rng::with_seed(seed).shuffle(&mut lines);
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squeeze_blank_lines_keeps_one_line_of_each_run() {
+        assert_eq!(
+            squeeze_blank_lines("a\n\n\n\nb\n\nc"),
+            (String::from("a\n\nb\n\nc"), 2)
+        );
+    }
+
+    #[test]
+    fn squeeze_blank_lines_counts_whitespace_only_lines_as_blank() {
+        // The first line of a run is kept as it is, whitespace and all
+        assert_eq!(
+            squeeze_blank_lines("a\n  \n\t\n \t \nb"),
+            (String::from("a\n  \nb"), 2)
+        );
+    }
+
+    #[test]
+    fn squeeze_blank_lines_squeezes_leading_and_trailing_runs() {
+        assert_eq!(
+            squeeze_blank_lines("\n\n\na\n\n\n"),
+            (String::from("\na\n"), 4)
+        );
+    }
+
+    #[test]
+    fn squeeze_blank_lines_leaves_text_without_runs() {
+        assert_eq!(
+            squeeze_blank_lines("a\n\nb\nc\n"),
+            (String::from("a\n\nb\nc\n"), 0)
+        );
+        assert_eq!(squeeze_blank_lines(""), (String::new(), 0));
+    }
+}